@@ -0,0 +1,111 @@
+use std::collections::HashMap;
+
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use hmac::{Hmac, Mac};
+use iop_client::{SignedParams, SigningKey};
+use sha2::Sha256;
+
+const APP_KEY: &str = "500000";
+const APP_SECRET: &str = "0123456789abcdef0123456789abcdef";
+const BASE_SYNC_URL: &str = "https://open-api.alibaba.com/sync";
+
+/// The parameters of a typical catalog sync call.
+fn business_params() -> Vec<(&'static str, String)> {
+    vec![
+        ("method", "alibaba.icbu.category.attribute.get".to_string()),
+        ("cat_id", "100003070".to_string()),
+        (
+            "access_token",
+            "50000000a12bCdEfGhIjKlMnOpQrStUvWxYz0123456789abcdefgh".to_string(),
+        ),
+        (
+            "country_request",
+            r#"{"language":"en_US","page":1}"#.to_string(),
+        ),
+    ]
+}
+
+/// Signing and URL building as done before `SignedParams`: the map is cloned for
+/// each step, re-sorted, and the HMAC key is derived from the secret every time.
+fn legacy_signed_url(params: HashMap<String, String>) -> String {
+    let sign = {
+        let payload = params.clone();
+        let mut sorted_vec: Vec<(&String, &String)> = payload.iter().collect();
+        sorted_vec.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut concatenated = String::new();
+        for (key, value) in sorted_vec {
+            concatenated.push_str(key);
+            concatenated.push_str(value);
+        }
+
+        let app_secret = APP_SECRET.to_string();
+        let mut mac = Hmac::<Sha256>::new_from_slice(app_secret.as_bytes()).unwrap();
+        mac.update(concatenated.as_bytes());
+        format!("{:X}", mac.finalize().into_bytes())
+    };
+
+    let mut url = String::from(BASE_SYNC_URL);
+    let mut first = true;
+    for (key, value) in params.clone() {
+        if first {
+            url.push_str(&format!("?{}={}", key, value));
+            first = false;
+        } else {
+            url.push_str(&format!("&{}={}", key, value));
+        }
+    }
+    url.push_str(&format!("&sign={}", sign));
+
+    url
+}
+
+fn legacy_params() -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    map.insert("app_key".to_string(), APP_KEY.to_string());
+    map.insert("sign_method".to_string(), "sha256".to_string());
+    map.insert("simplify".to_string(), "true".to_string());
+    map.insert("timestamp".to_string(), "1735689600000".to_string());
+    map.insert("language".to_string(), "en_US".to_string());
+    for (key, value) in business_params() {
+        map.insert(key.to_string(), value);
+    }
+    map
+}
+
+fn signed_params() -> SignedParams<'static> {
+    let mut params = SignedParams::with_capacity(10);
+    params.insert("app_key", APP_KEY);
+    params.insert("sign_method", "sha256");
+    params.insert("simplify", "true");
+    params.insert("timestamp", "1735689600000".to_string());
+    params.insert("language", "en_US");
+    for (key, value) in business_params() {
+        params.insert(key, value);
+    }
+    params
+}
+
+fn bench_signing(c: &mut Criterion) {
+    let key = SigningKey::new(APP_SECRET);
+
+    let mut group = c.benchmark_group("sign_and_build_url");
+    group.bench_function("legacy_hashmap", |b| {
+        b.iter_batched(
+            legacy_params,
+            |params| black_box(legacy_signed_url(params)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.bench_function("signed_params", |b| {
+        b.iter_batched(
+            signed_params,
+            |params| black_box(params.to_url(&key, BASE_SYNC_URL, None)),
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_signing);
+criterion_main!(benches);
@@ -0,0 +1,366 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::product_category::{CategoryAttribute, CategoryAttributeGroup, ValueType};
+
+/// A value given for a product attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ProductAttributeValue {
+    /// One of the predefined values, by `AttributeValue.attr_value_id`.
+    Id(i32),
+    /// A value typed in by the seller.
+    Custom(String),
+}
+
+/// The attributes of a product, keyed by `CategoryAttribute.attr_id`.
+pub type ProductAttributes = BTreeMap<i32, Vec<ProductAttributeValue>>;
+
+/// A rule of the category attribute schema broken by a product.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum AttributeViolation {
+    /// A required attribute has no value.
+    MissingRequired { attr_id: i32, name: String },
+    /// The attribute is not defined for the category.
+    UnknownAttribute { attr_id: i32 },
+    /// Several values were given to a single-valued attribute.
+    TooManyValues {
+        attr_id: i32,
+        name: String,
+        count: usize,
+    },
+    /// The value ID is not among the attribute's predefined values.
+    UnknownValue {
+        attr_id: i32,
+        name: String,
+        attr_value_id: i32,
+    },
+    /// A custom value was given to an attribute restricted to predefined values.
+    CustomValueNotAllowed {
+        attr_id: i32,
+        name: String,
+        value: String,
+    },
+    /// A custom value of a numeric attribute is not a number.
+    NotANumber {
+        attr_id: i32,
+        name: String,
+        value: String,
+    },
+}
+
+impl fmt::Display for AttributeViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AttributeViolation::MissingRequired { attr_id, name } => {
+                write!(f, "Attribute {name} ({attr_id}) is required")
+            }
+            AttributeViolation::UnknownAttribute { attr_id } => {
+                write!(f, "Attribute {attr_id} is not defined for the category")
+            }
+            AttributeViolation::TooManyValues {
+                attr_id,
+                name,
+                count,
+            } => write!(
+                f,
+                "Attribute {name} ({attr_id}) takes a single value, got {count}"
+            ),
+            AttributeViolation::UnknownValue {
+                attr_id,
+                name,
+                attr_value_id,
+            } => write!(
+                f,
+                "Value {attr_value_id} is not allowed for attribute {name} ({attr_id})"
+            ),
+            AttributeViolation::CustomValueNotAllowed {
+                attr_id,
+                name,
+                value,
+            } => write!(
+                f,
+                "Custom value {value:?} is not allowed for attribute {name} ({attr_id})"
+            ),
+            AttributeViolation::NotANumber {
+                attr_id,
+                name,
+                value,
+            } => write!(
+                f,
+                "Value {value:?} of attribute {name} ({attr_id}) is not a number"
+            ),
+        }
+    }
+}
+
+impl CategoryAttributeGroup {
+    /// Returns the attribute definition for `attr_id`, if the category has one.
+    pub fn attribute(&self, attr_id: i32) -> Option<&CategoryAttribute> {
+        self.attributes
+            .attribute
+            .iter()
+            .find(|attribute| attribute.attr_id == attr_id)
+    }
+
+    /// Checks a product's attributes against the category attribute schema.
+    ///
+    /// Required attributes must have a value, predefined values must come from
+    /// `attribute_values`, custom values are only accepted where `customize_value`
+    /// is set, and custom values of `number` attributes must parse as numbers.
+    ///
+    /// # Arguments
+    ///
+    /// * `attributes` - The product's attribute values, keyed by `attr_id`.
+    ///
+    /// # Returns
+    ///
+    /// Every violation found, empty if the attributes satisfy the category.
+    pub fn validate(&self, attributes: &ProductAttributes) -> Vec<AttributeViolation> {
+        let mut violations = Vec::new();
+
+        for attribute in &self.attributes.attribute {
+            let has_value = attributes
+                .get(&attribute.attr_id)
+                .map_or(false, |values| !values.is_empty());
+            if attribute.required && !has_value {
+                violations.push(AttributeViolation::MissingRequired {
+                    attr_id: attribute.attr_id,
+                    name: attribute.en_name.clone(),
+                });
+            }
+        }
+
+        for (attr_id, values) in attributes {
+            let attribute = match self.attribute(*attr_id) {
+                Some(attribute) => attribute,
+                None => {
+                    violations.push(AttributeViolation::UnknownAttribute { attr_id: *attr_id });
+                    continue;
+                }
+            };
+
+            if values.len() > 1 && !attribute.is_multi_valued() {
+                violations.push(AttributeViolation::TooManyValues {
+                    attr_id: *attr_id,
+                    name: attribute.en_name.clone(),
+                    count: values.len(),
+                });
+            }
+
+            for value in values {
+                if let Some(violation) = check_value(attribute, value) {
+                    violations.push(violation);
+                }
+            }
+        }
+
+        violations
+    }
+}
+
+/// Checks a single value against its attribute definition.
+pub(crate) fn check_value(
+    attribute: &CategoryAttribute,
+    value: &ProductAttributeValue,
+) -> Option<AttributeViolation> {
+    match value {
+        ProductAttributeValue::Id(attr_value_id) => {
+            let known = attribute.attribute_values.as_ref().map_or(false, |values| {
+                values
+                    .attribute_value
+                    .iter()
+                    .any(|value| value.attr_value_id == *attr_value_id)
+            });
+            if known {
+                return None;
+            }
+
+            Some(AttributeViolation::UnknownValue {
+                attr_id: attribute.attr_id,
+                name: attribute.en_name.clone(),
+                attr_value_id: *attr_value_id,
+            })
+        }
+        ProductAttributeValue::Custom(value) => {
+            if attribute.is_enumerated() && !attribute.customize_value {
+                return Some(AttributeViolation::CustomValueNotAllowed {
+                    attr_id: attribute.attr_id,
+                    name: attribute.en_name.clone(),
+                    value: value.clone(),
+                });
+            }
+
+            if attribute.value_type == ValueType::Number && value.trim().parse::<f64>().is_err() {
+                return Some(AttributeViolation::NotANumber {
+                    attr_id: attribute.attr_id,
+                    name: attribute.en_name.clone(),
+                    value: value.clone(),
+                });
+            }
+
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Material (1): required single-select. Colors (2): multi-select accepting
+    /// custom values. Weight (3): free numeric input.
+    fn schema() -> CategoryAttributeGroup {
+        serde_json::from_value(json!({
+            "attributes": { "attribute": [
+                {
+                    "attr_id": 1, "en_name": "Material", "sku_attribute": false,
+                    "required": true, "show_type": "list_box", "input_type": "single_select",
+                    "value_type": "string", "customize_image": false, "customize_value": false,
+                    "car_model": false,
+                    "attribute_values": { "attribute_value": [
+                        { "attr_value_id": 10, "en_name": "Steel", "sku_value": false },
+                        { "attr_value_id": 11, "en_name": "Wood", "sku_value": false },
+                    ] },
+                },
+                {
+                    "attr_id": 2, "en_name": "Colors", "sku_attribute": false,
+                    "required": false, "show_type": "check_box", "input_type": "multi_select",
+                    "value_type": "string", "customize_image": false, "customize_value": true,
+                    "car_model": false,
+                    "attribute_values": { "attribute_value": [
+                        { "attr_value_id": 20, "en_name": "Red", "sku_value": false },
+                        { "attr_value_id": 21, "en_name": "Blue", "sku_value": false },
+                    ] },
+                },
+                {
+                    "attr_id": 3, "en_name": "Weight", "sku_attribute": false,
+                    "required": false, "show_type": "input", "input_type": "input",
+                    "value_type": "number", "customize_image": false, "customize_value": true,
+                    "car_model": false, "attribute_values": {},
+                },
+            ] },
+        }))
+        .unwrap()
+    }
+
+    fn attributes(values: Vec<(i32, Vec<ProductAttributeValue>)>) -> ProductAttributes {
+        values.into_iter().collect()
+    }
+
+    #[test]
+    fn valid_attributes_pass() {
+        let attributes = attributes(vec![
+            (1, vec![ProductAttributeValue::Id(10)]),
+            (
+                2,
+                vec![
+                    ProductAttributeValue::Id(20),
+                    ProductAttributeValue::Custom("Olive".to_string()),
+                ],
+            ),
+            (3, vec![ProductAttributeValue::Custom(" 2.5 ".to_string())]),
+        ]);
+        assert!(schema().validate(&attributes).is_empty());
+    }
+
+    #[test]
+    fn missing_required_values_are_reported() {
+        let expected = vec![AttributeViolation::MissingRequired {
+            attr_id: 1,
+            name: "Material".to_string(),
+        }];
+        assert_eq!(schema().validate(&attributes(vec![])), expected);
+        assert_eq!(schema().validate(&attributes(vec![(1, vec![])])), expected);
+    }
+
+    #[test]
+    fn values_outside_the_enumeration_are_reported() {
+        let violations = schema().validate(&attributes(vec![
+            (1, vec![ProductAttributeValue::Id(20)]),
+            (3, vec![ProductAttributeValue::Id(30)]),
+        ]));
+        assert_eq!(
+            violations,
+            vec![
+                AttributeViolation::UnknownValue {
+                    attr_id: 1,
+                    name: "Material".to_string(),
+                    attr_value_id: 20,
+                },
+                AttributeViolation::UnknownValue {
+                    attr_id: 3,
+                    name: "Weight".to_string(),
+                    attr_value_id: 30,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn custom_values_need_customize_value() {
+        let violations = schema().validate(&attributes(vec![(
+            1,
+            vec![ProductAttributeValue::Custom("Bamboo".to_string())],
+        )]));
+        assert_eq!(
+            violations,
+            vec![AttributeViolation::CustomValueNotAllowed {
+                attr_id: 1,
+                name: "Material".to_string(),
+                value: "Bamboo".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn numeric_custom_values_must_parse() {
+        let violations = schema().validate(&attributes(vec![
+            (1, vec![ProductAttributeValue::Id(10)]),
+            (3, vec![ProductAttributeValue::Custom("heavy".to_string())]),
+        ]));
+        assert_eq!(
+            violations,
+            vec![AttributeViolation::NotANumber {
+                attr_id: 3,
+                name: "Weight".to_string(),
+                value: "heavy".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn only_multi_valued_attributes_take_several_values() {
+        let violations = schema().validate(&attributes(vec![
+            (
+                1,
+                vec![ProductAttributeValue::Id(10), ProductAttributeValue::Id(11)],
+            ),
+            (
+                2,
+                vec![ProductAttributeValue::Id(20), ProductAttributeValue::Id(21)],
+            ),
+        ]));
+        assert_eq!(
+            violations,
+            vec![AttributeViolation::TooManyValues {
+                attr_id: 1,
+                name: "Material".to_string(),
+                count: 2,
+            }]
+        );
+    }
+
+    #[test]
+    fn unknown_attributes_are_reported() {
+        let violations = schema().validate(&attributes(vec![
+            (1, vec![ProductAttributeValue::Id(10)]),
+            (99, vec![ProductAttributeValue::Custom("x".to_string())]),
+        ]));
+        assert_eq!(
+            violations,
+            vec![AttributeViolation::UnknownAttribute { attr_id: 99 }]
+        );
+    }
+}
@@ -0,0 +1,197 @@
+use serde::{Deserialize, Serialize};
+
+use crate::category_tree::{CategoryNode, CategoryTree};
+
+/// A category present in only one of the compared snapshots.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryEntry {
+    pub category_id: i32,
+    pub name: String,
+    pub path: String,
+    pub leaf_category: bool,
+}
+
+/// A category whose English or Chinese name changed.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryRenamed {
+    pub category_id: i32,
+    pub old_name: String,
+    pub new_name: String,
+    pub old_cn_name: Option<String>,
+    pub new_cn_name: Option<String>,
+}
+
+/// A category that now sits under a different parent.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryMoved {
+    pub category_id: i32,
+    pub old_parent_id: Option<i32>,
+    pub new_parent_id: Option<i32>,
+    pub old_path: String,
+    pub new_path: String,
+}
+
+/// A category that became a leaf, or stopped being one (typically after a split).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryLeafChanged {
+    pub category_id: i32,
+    pub name: String,
+    pub was_leaf: bool,
+    pub is_leaf: bool,
+}
+
+/// Why a mapped category can no longer be used.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MappingProblem {
+    /// The category was retired.
+    Removed,
+    /// The category was split and products must now use one of its children.
+    NoLongerLeaf,
+}
+
+/// A product mapping pointing to a category affected by a taxonomy change.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BrokenMapping {
+    pub category_id: i32,
+    pub problem: MappingProblem,
+}
+
+/// The changes between two category tree snapshots, each list in ID order.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct CategoryDiff {
+    pub added: Vec<CategoryEntry>,
+    pub removed: Vec<CategoryEntry>,
+    pub renamed: Vec<CategoryRenamed>,
+    pub moved: Vec<CategoryMoved>,
+    pub leaf_changed: Vec<CategoryLeafChanged>,
+}
+
+impl CategoryDiff {
+    /// Returns `true` if the snapshots describe the same taxonomy.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.renamed.is_empty()
+            && self.moved.is_empty()
+            && self.leaf_changed.is_empty()
+    }
+
+    /// Checks mapped category IDs against the changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `category_ids` - The category IDs our product mappings point to.
+    ///
+    /// # Returns
+    ///
+    /// The mappings pointing to removed categories, or to categories that are no
+    /// longer leaves and thus no longer accept products.
+    pub fn broken_mappings(
+        &self,
+        category_ids: impl IntoIterator<Item = i32>,
+    ) -> Vec<BrokenMapping> {
+        let mut broken = Vec::new();
+        for category_id in category_ids {
+            let problem = if self
+                .removed
+                .iter()
+                .any(|entry| entry.category_id == category_id)
+            {
+                MappingProblem::Removed
+            } else if self
+                .leaf_changed
+                .iter()
+                .any(|change| change.category_id == category_id && !change.is_leaf)
+            {
+                MappingProblem::NoLongerLeaf
+            } else {
+                continue;
+            };
+
+            broken.push(BrokenMapping {
+                category_id,
+                problem,
+            });
+        }
+        broken
+    }
+}
+
+impl CategoryTree {
+    /// Compares this snapshot with a newer one.
+    ///
+    /// Both snapshots should come from crawls of the same root to the same depth;
+    /// otherwise categories outside the shallower crawl show up as added or removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `newer` - The more recent snapshot.
+    ///
+    /// # Returns
+    ///
+    /// A `CategoryDiff` listing added, removed, renamed, moved and leaf-status-changed
+    /// categories.
+    pub fn diff(&self, newer: &CategoryTree) -> CategoryDiff {
+        let mut diff = CategoryDiff::default();
+
+        for (id, old) in &self.nodes {
+            let new = match newer.get(*id) {
+                Some(new) => new,
+                None => {
+                    diff.removed.push(self.entry(old));
+                    continue;
+                }
+            };
+
+            if old.category.name != new.category.name
+                || old.category.cn_name != new.category.cn_name
+            {
+                diff.renamed.push(CategoryRenamed {
+                    category_id: *id,
+                    old_name: old.category.name.clone(),
+                    new_name: new.category.name.clone(),
+                    old_cn_name: old.category.cn_name.clone(),
+                    new_cn_name: new.category.cn_name.clone(),
+                });
+            }
+
+            if old.parent_id != new.parent_id {
+                diff.moved.push(CategoryMoved {
+                    category_id: *id,
+                    old_parent_id: old.parent_id,
+                    new_parent_id: new.parent_id,
+                    old_path: self.breadcrumb(*id, " > ").unwrap_or_default(),
+                    new_path: newer.breadcrumb(*id, " > ").unwrap_or_default(),
+                });
+            }
+
+            if old.category.leaf_category != new.category.leaf_category {
+                diff.leaf_changed.push(CategoryLeafChanged {
+                    category_id: *id,
+                    name: new.category.name.clone(),
+                    was_leaf: old.category.leaf_category,
+                    is_leaf: new.category.leaf_category,
+                });
+            }
+        }
+
+        for (id, new) in &newer.nodes {
+            if self.get(*id).is_none() {
+                diff.added.push(newer.entry(new));
+            }
+        }
+
+        diff
+    }
+
+    fn entry(&self, node: &CategoryNode) -> CategoryEntry {
+        CategoryEntry {
+            category_id: node.category.category_id,
+            name: node.category.name.clone(),
+            path: self
+                .breadcrumb(node.category.category_id, " > ")
+                .unwrap_or_default(),
+            leaf_category: node.category.leaf_category,
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::{
+    collections::BTreeMap,
+    io::{BufRead, Read, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    category_tree::{CategoryNode, CategoryTree},
+    product_category::{NewCategory, NewCategoryChildId},
+};
+
+/// A category with its children nested inline, as written by `write_nested_json`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NestedCategory {
+    pub category_id: i32,
+    pub parent_id: Option<i32>,
+    pub level: i32,
+    pub leaf_category: bool,
+    pub name: String,
+    pub cn_name: Option<String>,
+
+    #[serde(default)]
+    pub children: Vec<NestedCategory>,
+}
+
+const CSV_HEADER: &str = "category_id,parent_id,level,leaf_category,name,cn_name,path";
+
+impl CategoryTree {
+    /// Converts the tree into nested categories, starting at the root.
+    pub fn to_nested(&self) -> Option<NestedCategory> {
+        self.root().map(|root| self.nest(root))
+    }
+
+    fn nest(&self, node: &CategoryNode) -> NestedCategory {
+        NestedCategory {
+            category_id: node.category.category_id,
+            parent_id: node.parent_id,
+            level: node.category.level,
+            leaf_category: node.category.leaf_category,
+            name: node.category.name.clone(),
+            cn_name: node.category.cn_name.clone(),
+            children: self
+                .children(node.category.category_id)
+                .into_iter()
+                .map(|child| self.nest(child))
+                .collect(),
+        }
+    }
+
+    /// Writes the tree as a single nested JSON document.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The destination, e.g. a `File` or a `Vec<u8>`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Ok` if the document was written, or an error if
+    /// serialization or writing fails.
+    pub fn write_nested_json<W: Write>(&self, writer: W) -> Result<(), Box<dyn std::error::Error>> {
+        serde_json::to_writer_pretty(writer, &self.to_nested())?;
+        Ok(())
+    }
+
+    /// Writes the tree as flat CSV, one row per category in ID order.
+    ///
+    /// The columns are `category_id`, `parent_id`, `level`, `leaf_category`, `name`,
+    /// `cn_name` and `path`, the latter being the English names from the root joined
+    /// with `" > "`.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The destination, e.g. a `File` or a `Vec<u8>`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Ok` if every row was written, or an error if writing fails.
+    pub fn write_csv<W: Write>(&self, mut writer: W) -> Result<(), Box<dyn std::error::Error>> {
+        writeln!(writer, "{}", CSV_HEADER)?;
+
+        for (id, node) in &self.nodes {
+            let path = self
+                .path(*id)
+                .iter()
+                .map(|node| node.category.name.as_str())
+                .collect::<Vec<_>>()
+                .join(" > ");
+
+            writeln!(
+                writer,
+                "{},{},{},{},{},{},{}",
+                id,
+                node.parent_id.map(|id| id.to_string()).unwrap_or_default(),
+                node.category.level,
+                node.category.leaf_category,
+                csv_field(&node.category.name),
+                csv_field(node.category.cn_name.as_deref().unwrap_or_default()),
+                csv_field(&path),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the tree as JSON Lines, one `CategoryNode` per line in ID order.
+    ///
+    /// Unlike the nested and CSV formats this keeps every field of the crawled
+    /// categories, so `read_json_lines` restores an identical tree.
+    ///
+    /// # Arguments
+    ///
+    /// * `writer` - The destination, e.g. a `File` or a `Vec<u8>`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Ok` if every line was written, or an error if serialization
+    /// or writing fails.
+    pub fn write_json_lines<W: Write>(
+        &self,
+        mut writer: W,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        for node in self.nodes.values() {
+            serde_json::to_writer(&mut writer, node)?;
+            writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    /// Loads a tree written by `write_json_lines`.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The source, e.g. a `BufReader<File>`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `CategoryTree` if successful, or an error if a line
+    /// cannot be parsed or no root (a node without parent) is present.
+    pub fn read_json_lines<R: BufRead>(reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        let mut nodes = BTreeMap::new();
+        let mut root_id = None;
+
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let node: CategoryNode = serde_json::from_str(&line)?;
+            if node.parent_id.is_none() {
+                root_id = Some(node.category.category_id);
+            }
+            nodes.insert(node.category.category_id, node);
+        }
+
+        match root_id {
+            Some(root_id) => Ok(CategoryTree { root_id, nodes }),
+            None => Err("Category snapshot has no root".into()),
+        }
+    }
+
+    /// Loads a tree written by `write_nested_json`.
+    ///
+    /// The nested format does not carry `parent_ids`, so they are left unset and
+    /// ancestors must be found through `CategoryTree::path`; `child_ids` are rebuilt
+    /// from the nested children.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The source, e.g. a `File`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `CategoryTree` if successful, or an error if the
+    /// document cannot be parsed or is empty.
+    pub fn read_nested_json<R: Read>(reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        let root: Option<NestedCategory> = serde_json::from_reader(reader)?;
+        let root = match root {
+            Some(root) => root,
+            None => return Err("Category snapshot has no root".into()),
+        };
+
+        let mut tree = CategoryTree {
+            root_id: root.category_id,
+            nodes: BTreeMap::new(),
+        };
+        let mut stack = vec![(root, 0)];
+        while let Some((nested, depth)) = stack.pop() {
+            let category = NewCategory {
+                leaf_category: nested.leaf_category,
+                cn_name: nested.cn_name,
+                category_id: nested.category_id,
+                level: nested.level,
+                name: nested.name,
+                child_ids: id_list(nested.children.iter().map(|child| child.category_id)),
+                parent_ids: None,
+            };
+            tree.insert(category, nested.parent_id, depth);
+
+            for child in nested.children {
+                stack.push((child, depth + 1));
+            }
+        }
+
+        Ok(tree)
+    }
+}
+
+fn id_list(ids: impl IntoIterator<Item = i32>) -> Option<NewCategoryChildId> {
+    let number: Vec<String> = ids.into_iter().map(|id| id.to_string()).collect();
+    if number.is_empty() {
+        None
+    } else {
+        Some(NewCategoryChildId { number })
+    }
+}
+
+/// Quotes a CSV field when it contains a separator, quote or line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn category(category_id: i32, level: i32, children: &[i32], parents: &[i32]) -> NewCategory {
+        serde_json::from_value(json!({
+            "leaf_category": children.is_empty(),
+            "cn_name": format!("类目{}", category_id),
+            "category_id": category_id,
+            "level": level,
+            "name": format!("Category, {}", category_id),
+            "child_ids": { "number": children.iter().map(|id| id.to_string()).collect::<Vec<_>>() },
+            "parent_ids": { "number": parents.iter().map(|id| id.to_string()).collect::<Vec<_>>() },
+        }))
+        .unwrap()
+    }
+
+    fn tree() -> CategoryTree {
+        let mut tree = CategoryTree::new(category(1, 1, &[2, 3], &[]));
+        tree.insert(category(2, 2, &[4], &[1]), Some(1), 1);
+        tree.insert(category(3, 2, &[], &[1]), Some(1), 1);
+        tree.insert(category(4, 3, &[], &[1, 2]), Some(2), 2);
+        tree
+    }
+
+    fn ids(list: &Option<NewCategoryChildId>) -> Vec<String> {
+        list.as_ref()
+            .map(|list| list.number.clone())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn json_lines_round_trip_is_lossless() {
+        let tree = tree();
+        let mut buffer = Vec::new();
+        tree.write_json_lines(&mut buffer).unwrap();
+        assert_eq!(buffer.iter().filter(|byte| **byte == b'\n').count(), 4);
+
+        let restored = CategoryTree::read_json_lines(buffer.as_slice()).unwrap();
+        assert_eq!(
+            serde_json::to_value(&restored).unwrap(),
+            serde_json::to_value(&tree).unwrap()
+        );
+    }
+
+    #[test]
+    fn json_lines_without_root_is_rejected() {
+        let node = serde_json::to_string(tree().get(2).unwrap()).unwrap();
+        assert!(CategoryTree::read_json_lines(node.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn nested_round_trip_keeps_structure() {
+        let tree = tree();
+        let mut buffer = Vec::new();
+        tree.write_nested_json(&mut buffer).unwrap();
+
+        let restored = CategoryTree::read_nested_json(buffer.as_slice()).unwrap();
+        assert_eq!(restored.root_id, 1);
+        assert_eq!(restored.len(), tree.len());
+        for (id, node) in &tree.nodes {
+            let restored = restored.get(*id).unwrap();
+            assert_eq!(restored.parent_id, node.parent_id);
+            assert_eq!(restored.depth, node.depth);
+            assert_eq!(restored.children, node.children);
+            assert_eq!(restored.category.name, node.category.name);
+            assert_eq!(restored.category.cn_name, node.category.cn_name);
+            assert_eq!(restored.category.level, node.category.level);
+            assert_eq!(
+                ids(&restored.category.child_ids),
+                ids(&node.category.child_ids)
+            );
+            assert!(restored.category.parent_ids.is_none());
+        }
+
+        let path: Vec<i32> = restored
+            .path(4)
+            .iter()
+            .map(|node| node.category.category_id)
+            .collect();
+        assert_eq!(path, vec![1, 2, 4]);
+    }
+
+    #[test]
+    fn csv_quotes_fields_and_writes_paths() {
+        let mut buffer = Vec::new();
+        tree().write_csv(&mut buffer).unwrap();
+        let csv = String::from_utf8(buffer).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], CSV_HEADER);
+        assert_eq!(
+            lines[4],
+            "4,2,3,true,\"Category, 4\",类目4,\"Category, 1 > Category, 2 > Category, 4\""
+        );
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    collections::{HashMap, HashSet},
+    future::Future,
+};
+
+use futures::{stream, StreamExt};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{methods, urls},
+    product_category::CategoryAttribute,
+    product_group::empty_object_as_none,
+    IopClient,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CategoryLevelAttrGetResponse {
+    alibaba_icbu_category_level_attr_get_response: CategoryLevelAttrResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CategoryLevelAttrResult {
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    result_list: Option<LevelAttributeValues>,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LevelAttributeValues {
+    level_attr_value: Vec<LevelAttributeValue>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct AttributeValueRequest {
+    cat_id: i32,
+    attr_id: i32,
+    value_id: i32,
+}
+
+/// A child value of a hierarchical attribute, e.g. a model below a brand.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LevelAttributeValue {
+    pub attr_value_id: i32,
+    pub en_name: String,
+
+    /// `true` if the value has no further children.
+    #[serde(default)]
+    pub leaf: bool,
+}
+
+/// A value of a hierarchical attribute with its children expanded.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttributeValueNode {
+    pub attr_value_id: i32,
+    pub en_name: String,
+    pub children: Vec<AttributeValueNode>,
+}
+
+/// The fully expanded values of one hierarchical attribute.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AttributeCascade {
+    pub attr_id: i32,
+    pub en_name: String,
+    pub values: Vec<AttributeValueNode>,
+}
+
+impl IopClient {
+    /// 类目层级属性获取
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.category.level.attr.get&methodType=GET/POST)
+    ///
+    /// Retrieves the child values of a hierarchical attribute below a parent value.
+    ///
+    /// # Arguments
+    ///
+    /// * `cat_id` - The category ID.
+    /// * `attr_id` - The `CategoryAttribute.attr_id` of the hierarchical attribute.
+    /// * `value_id` - The parent value, e.g. a brand's `attr_value_id`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the child values if successful, empty if the parent value
+    /// is a leaf, or an error if the process fails.
+    pub async fn get_level_attribute_values(
+        &self,
+        cat_id: i32,
+        attr_id: i32,
+        value_id: i32,
+    ) -> Result<Vec<LevelAttributeValue>, Box<dyn std::error::Error>> {
+        let request = serde_json::to_string(&AttributeValueRequest {
+            cat_id,
+            attr_id,
+            value_id,
+        })?;
+
+        let mut params = self.build_signed_params().await;
+        params.insert("attribute_value_request", request);
+        params.insert("method", methods::ALIBABA_ICBU_CATEGORY_LEVEL_ATTR_GET);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------get_level_attribute_values-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<CategoryLevelAttrGetResponse>(&body)?;
+
+        Ok(result
+            .alibaba_icbu_category_level_attr_get_response
+            .result_list
+            .map(|list| list.level_attr_value)
+            .unwrap_or_default())
+    }
+
+    /// Expands the values of a hierarchical attribute down to the leaves.
+    ///
+    /// The top-level values come from `get_category_attributes`; every further level
+    /// is fetched with `get_level_attribute_values`, one request per non-leaf value,
+    /// keeping at most `concurrency` requests in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `cat_id` - The category ID.
+    /// * `attr_id` - The `CategoryAttribute.attr_id` of the hierarchical attribute.
+    /// * `concurrency` - The maximum number of concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the expanded `AttributeCascade` if successful, or an error
+    /// if the attribute is not defined for the category or a request fails.
+    pub async fn expand_attribute_cascade(
+        &self,
+        cat_id: i32,
+        attr_id: i32,
+        concurrency: usize,
+    ) -> Result<AttributeCascade, Box<dyn std::error::Error>> {
+        let attributes = self.get_category_attributes(cat_id).await?;
+        let attribute = match attributes.attribute(attr_id) {
+            Some(attribute) => attribute,
+            None => {
+                return Err(format!("Attribute {attr_id} not found in category {cat_id}").into())
+            }
+        };
+
+        self.expand_cascade(cat_id, attribute, concurrency).await
+    }
+
+    /// Expands the hierarchical attributes of a category: those flagged `car_model`
+    /// in the schema and those listed in `attr_ids`, e.g. a brand attribute whose
+    /// values have models below them.
+    ///
+    /// # Arguments
+    ///
+    /// * `cat_id` - The category ID.
+    /// * `attr_ids` - Further attributes to expand, by `CategoryAttribute.attr_id`.
+    /// * `concurrency` - The maximum number of concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing one `AttributeCascade` per expanded attribute if
+    /// successful, or an error if a request fails.
+    pub async fn expand_category_cascades(
+        &self,
+        cat_id: i32,
+        attr_ids: &[i32],
+        concurrency: usize,
+    ) -> Result<Vec<AttributeCascade>, Box<dyn std::error::Error>> {
+        let attributes = self.get_category_attributes(cat_id).await?;
+
+        let mut cascades = Vec::new();
+        for attribute in &attributes.attributes.attribute {
+            if attribute.is_hierarchical() || attr_ids.contains(&attribute.attr_id) {
+                cascades.push(self.expand_cascade(cat_id, attribute, concurrency).await?);
+            }
+        }
+
+        Ok(cascades)
+    }
+
+    async fn expand_cascade(
+        &self,
+        cat_id: i32,
+        attribute: &CategoryAttribute,
+        concurrency: usize,
+    ) -> Result<AttributeCascade, Box<dyn std::error::Error>> {
+        let attr_id = attribute.attr_id;
+        expand(attribute, concurrency, |value_id| async move {
+            self.get_level_attribute_values(cat_id, attr_id, value_id)
+                .await
+                .map_err(|err| err.to_string())
+        })
+        .await
+    }
+}
+
+/// A value of a cascade, keyed by its parent value (`None` at the top level) so
+/// that a value ID listed under several parents is expanded under each of them.
+type ValueKey = (Option<i32>, i32);
+
+/// Fetches the levels below the top-level values of `attribute` breadth-first
+/// through `fetch`, keeping at most `concurrency` requests in flight.
+async fn expand<F, Fut>(
+    attribute: &CategoryAttribute,
+    concurrency: usize,
+    fetch: F,
+) -> Result<AttributeCascade, Box<dyn std::error::Error>>
+where
+    F: Fn(i32) -> Fut,
+    Fut: Future<Output = Result<Vec<LevelAttributeValue>, String>>,
+{
+    let top: Vec<ValueKey> = attribute
+        .attribute_values
+        .iter()
+        .flat_map(|values| &values.attribute_value)
+        .map(|value| (None, value.attr_value_id))
+        .collect();
+
+    let mut children: HashMap<ValueKey, Vec<LevelAttributeValue>> = HashMap::new();
+    let mut requested: HashSet<ValueKey> = top.iter().copied().collect();
+    let mut level = top;
+
+    while !level.is_empty() {
+        let results: Vec<_> = stream::iter(level)
+            .map(|key| {
+                let request = fetch(key.1);
+                async move { (key, request.await) }
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+
+        level = Vec::new();
+        for (key, result) in results {
+            let values = result?;
+            level.extend(
+                values
+                    .iter()
+                    .map(|value| (Some(key.1), value.attr_value_id))
+                    .zip(&values)
+                    .filter(|(child, value)| !value.leaf && requested.insert(*child))
+                    .map(|(child, _)| child),
+            );
+            children.insert(key, values);
+        }
+    }
+
+    info!(
+        "--------expand_attribute_cascade-------- attr_id: {}, requests: {}",
+        attribute.attr_id,
+        requested.len()
+    );
+
+    Ok(AttributeCascade {
+        attr_id: attribute.attr_id,
+        en_name: attribute.en_name.clone(),
+        values: attribute
+            .attribute_values
+            .iter()
+            .flat_map(|values| &values.attribute_value)
+            .map(|value| {
+                assemble(
+                    (None, value.attr_value_id),
+                    value.en_name.clone(),
+                    &children,
+                    &mut Vec::new(),
+                )
+            })
+            .collect(),
+    })
+}
+
+/// Builds the node for `key` from the fetched children, stopping at values that
+/// already appear among its ancestors.
+fn assemble(
+    key: ValueKey,
+    en_name: String,
+    children: &HashMap<ValueKey, Vec<LevelAttributeValue>>,
+    ancestors: &mut Vec<ValueKey>,
+) -> AttributeValueNode {
+    let values = match children.get(&key) {
+        Some(values) if !ancestors.contains(&key) => values.as_slice(),
+        _ => &[],
+    };
+
+    ancestors.push(key);
+    let nodes = values
+        .iter()
+        .map(|value| {
+            assemble(
+                (Some(key.1), value.attr_value_id),
+                value.en_name.clone(),
+                children,
+                ancestors,
+            )
+        })
+        .collect();
+    ancestors.pop();
+
+    AttributeValueNode {
+        attr_value_id: key.1,
+        en_name,
+        children: nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn brand() -> CategoryAttribute {
+        serde_json::from_value(json!({
+            "attr_id": 2, "en_name": "Brand Name", "sku_attribute": false,
+            "required": false, "show_type": "list_box", "input_type": "single_select",
+            "value_type": "string", "customize_image": false, "customize_value": false,
+            "car_model": false,
+            "attribute_values": { "attribute_value": [
+                { "attr_value_id": 1, "en_name": "Toyota", "sku_value": false },
+                { "attr_value_id": 2, "en_name": "Honda", "sku_value": false },
+            ] },
+        }))
+        .unwrap()
+    }
+
+    fn value(attr_value_id: i32, en_name: &str, leaf: bool) -> LevelAttributeValue {
+        LevelAttributeValue {
+            attr_value_id,
+            en_name: en_name.to_string(),
+            leaf,
+        }
+    }
+
+    /// Both brands list the shared value "Other" (99), which has a trim level with
+    /// children of its own; 30 and 31 list each other.
+    fn levels(value_id: i32) -> Vec<LevelAttributeValue> {
+        match value_id {
+            1 => vec![value(10, "Corolla", true), value(99, "Other", false)],
+            2 => vec![value(20, "Civic", false), value(99, "Other", false)],
+            20 => vec![value(30, "Loop", false)],
+            30 => vec![value(31, "Back", false)],
+            31 => vec![value(30, "Loop", false)],
+            99 => vec![value(100, "Base", false)],
+            100 => vec![value(101, "Standard", true)],
+            _ => vec![],
+        }
+    }
+
+    fn names(nodes: &[AttributeValueNode]) -> Vec<&str> {
+        nodes.iter().map(|node| node.en_name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn values_shared_by_several_parents_are_expanded_under_each() {
+        let calls = Mutex::new(Vec::new());
+        let cascade = expand(&brand(), 3, |value_id| {
+            calls.lock().unwrap().push(value_id);
+            let values = levels(value_id);
+            async move { Ok(values) }
+        })
+        .await
+        .unwrap();
+
+        assert_eq!(names(&cascade.values), vec!["Toyota", "Honda"]);
+        for brand in &cascade.values {
+            let other = brand.children.iter().find(|node| node.attr_value_id == 99);
+            let base = &other.unwrap().children[0];
+            assert_eq!(base.en_name, "Base");
+            assert_eq!(names(&base.children), vec!["Standard"]);
+        }
+
+        let calls = calls.into_inner().unwrap();
+        assert!(!calls.contains(&10), "leaf values are not fetched");
+    }
+
+    #[tokio::test]
+    async fn cycles_terminate() {
+        let cascade = expand(&brand(), 1, |value_id| {
+            let values = levels(value_id);
+            async move { Ok(values) }
+        })
+        .await
+        .unwrap();
+
+        let civic = &cascade.values[1].children[0];
+        let back = &civic.children[0].children[0];
+        assert_eq!(back.en_name, "Back");
+        assert_eq!(names(&back.children), vec!["Loop"]);
+        assert!(back.children[0]
+            .children
+            .iter()
+            .all(|node| node.children.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn failed_requests_are_reported() {
+        let result = expand(&brand(), 2, |value_id| async move {
+            if value_id == 2 {
+                Err("timeout".to_string())
+            } else {
+                Ok(levels(value_id))
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "timeout");
+    }
+
+    #[test]
+    fn only_car_model_attributes_are_hierarchical() {
+        assert!(!brand().is_hierarchical());
+
+        let mut car_model = brand();
+        car_model.car_model = true;
+        assert!(car_model.is_hierarchical());
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use deadpool_redis::redis::cmd;
+use futures::{stream, StreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{caches, keys, methods, urls},
+    product_category::NewCategory,
+    IopClient,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CategoryIdMappingResponse {
+    alibaba_icbu_category_id_mapping_response: CategoryIdMappingResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct CategoryIdMappingResult {
+    result: i32,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+/// 老类目ID转新类目ID
+const CONVERT_OLD_TO_NEW: &str = "1";
+
+impl IopClient {
+    /// 类目ID映射
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.category.id.mapping&methodType=GET/POST)
+    ///
+    /// Maps a category ID of the old ICBU category tree to the new tree.
+    ///
+    /// Each mapping is cached in Redis under its own key for a day, so repeated
+    /// lookups of the same legacy ID do not reach the gateway.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_cat_id` - The category ID in the old tree.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the category ID in the new tree if successful, or an error
+    /// if the process fails.
+    pub async fn map_category_id(
+        &self,
+        old_cat_id: i32,
+    ) -> Result<i32, Box<dyn std::error::Error>> {
+        if let Ok(mut conn) = self.pool.get().await {
+            match cmd("GET")
+                .arg(mapping_key(&self.appid, old_cat_id))
+                .query_async::<Option<i32>>(&mut conn)
+                .await
+            {
+                Ok(Some(new_cat_id)) => return Ok(new_cat_id),
+                Ok(None) => {}
+                Err(err) => warn!("Failed to read category id mapping, {err}"),
+            }
+        }
+
+        self.fetch_category_id_mapping(old_cat_id).await
+    }
+
+    /// Maps a list of old category IDs to categories of the new tree.
+    ///
+    /// Cached mappings are read in a single `MGET`; the remaining IDs go through the
+    /// mapping API, then each distinct new category is fetched once with
+    /// `list_product_categories`. At most `concurrency` requests are in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `old_cat_ids` - The category IDs in the old tree, e.g. from a legacy catalog.
+    /// * `concurrency` - The maximum number of concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `NewCategory` for each old ID if successful, or an
+    /// error if any lookup fails.
+    pub async fn map_category_ids(
+        &self,
+        old_cat_ids: &[i32],
+        concurrency: usize,
+    ) -> Result<BTreeMap<i32, NewCategory>, Box<dyn std::error::Error>> {
+        let old_cat_ids: Vec<i32> = old_cat_ids
+            .iter()
+            .copied()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect();
+        let mut new_cat_ids = self.cached_category_id_mappings(&old_cat_ids).await;
+
+        let missing = old_cat_ids
+            .iter()
+            .copied()
+            .filter(|old_cat_id| !new_cat_ids.contains_key(old_cat_id));
+        let mapped: Vec<_> = stream::iter(missing)
+            .map(|old_cat_id| async move {
+                let result = self
+                    .fetch_category_id_mapping(old_cat_id)
+                    .await
+                    .map_err(|err| err.to_string());
+                (old_cat_id, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        for (old_cat_id, result) in mapped {
+            new_cat_ids.insert(old_cat_id, result?);
+        }
+
+        let distinct: BTreeSet<i32> = new_cat_ids.values().copied().collect();
+        let fetched: Vec<_> = stream::iter(distinct)
+            .map(|new_cat_id| async move {
+                let result = self
+                    .list_product_categories(new_cat_id)
+                    .await
+                    .map_err(|err| err.to_string());
+                (new_cat_id, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+        let mut categories = HashMap::new();
+        for (new_cat_id, result) in fetched {
+            categories.insert(new_cat_id, result?);
+        }
+
+        Ok(assign_categories(&new_cat_ids, &categories))
+    }
+
+    /// Calls the mapping API and caches the result.
+    async fn fetch_category_id_mapping(
+        &self,
+        old_cat_id: i32,
+    ) -> Result<i32, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("cat_id", old_cat_id.to_string());
+        params.insert("convert_type", CONVERT_OLD_TO_NEW);
+        params.insert("method", methods::ALIBABA_ICBU_CATEGORY_ID_MAPPING);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------map_category_id-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<CategoryIdMappingResponse>(&body)?;
+        let new_cat_id = result.alibaba_icbu_category_id_mapping_response.result;
+
+        if let Ok(mut conn) = self.pool.get().await {
+            let cached = cmd("SETEX")
+                .arg(mapping_key(&self.appid, old_cat_id))
+                .arg(caches::ONE_DAY_IN_SECONDS)
+                .arg(new_cat_id)
+                .query_async::<()>(&mut conn)
+                .await;
+            if let Err(err) = cached {
+                warn!("Failed to cache category id mapping, {err}");
+            }
+        }
+
+        Ok(new_cat_id)
+    }
+
+    /// Reads the cached mappings of `old_cat_ids`, skipping the cache on errors.
+    async fn cached_category_id_mappings(&self, old_cat_ids: &[i32]) -> BTreeMap<i32, i32> {
+        if old_cat_ids.is_empty() {
+            return BTreeMap::new();
+        }
+
+        let mut conn = match self.pool.get().await {
+            Ok(conn) => conn,
+            Err(_) => return BTreeMap::new(),
+        };
+
+        let keys: Vec<String> = old_cat_ids
+            .iter()
+            .map(|old_cat_id| mapping_key(&self.appid, *old_cat_id))
+            .collect();
+        match cmd("MGET")
+            .arg(&keys)
+            .query_async::<Vec<Option<i32>>>(&mut conn)
+            .await
+        {
+            Ok(cached) => cached_mappings(old_cat_ids, cached),
+            Err(err) => {
+                warn!("Failed to read category id mappings, {err}");
+                BTreeMap::new()
+            }
+        }
+    }
+}
+
+/// The Redis key caching the mapping of `old_cat_id`.
+fn mapping_key(appid: &str, old_cat_id: i32) -> String {
+    format!("{}:{}:{}", keys::CATEGORY_ID_MAPPING, appid, old_cat_id)
+}
+
+/// Pairs the IDs given to `MGET` with the values it returned, dropping misses.
+fn cached_mappings(old_cat_ids: &[i32], cached: Vec<Option<i32>>) -> BTreeMap<i32, i32> {
+    old_cat_ids
+        .iter()
+        .zip(cached)
+        .filter_map(|(old_cat_id, new_cat_id)| {
+            new_cat_id.map(|new_cat_id| (*old_cat_id, new_cat_id))
+        })
+        .collect()
+}
+
+/// Looks up the new category of every old ID in `categories`, keyed by new ID.
+fn assign_categories(
+    new_cat_ids: &BTreeMap<i32, i32>,
+    categories: &HashMap<i32, NewCategory>,
+) -> BTreeMap<i32, NewCategory> {
+    new_cat_ids
+        .iter()
+        .filter_map(|(old_cat_id, new_cat_id)| {
+            categories
+                .get(new_cat_id)
+                .map(|category| (*old_cat_id, category.clone()))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn category(category_id: i32) -> NewCategory {
+        serde_json::from_value(json!({
+            "leaf_category": true,
+            "category_id": category_id,
+            "level": 3,
+            "name": format!("Category {}", category_id),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn each_mapping_has_its_own_key() {
+        assert_eq!(
+            mapping_key("500123", 42),
+            "iop:client:category_id_mapping:500123:42"
+        );
+        assert_ne!(mapping_key("500123", 42), mapping_key("500123", 43));
+        assert_ne!(mapping_key("500123", 42), mapping_key("500124", 42));
+    }
+
+    #[test]
+    fn cached_mappings_skip_misses() {
+        let cached = cached_mappings(&[1, 2, 3], vec![Some(10), None, Some(30)]);
+        assert_eq!(cached, BTreeMap::from([(1, 10), (3, 30)]));
+    }
+
+    #[test]
+    fn old_ids_sharing_a_new_category_get_a_copy_each() {
+        let new_cat_ids = BTreeMap::from([(1, 10), (2, 10), (3, 30)]);
+        let categories = HashMap::from([(10, category(10)), (30, category(30))]);
+
+        let assigned = assign_categories(&new_cat_ids, &categories);
+        let ids: Vec<(i32, i32)> = assigned
+            .iter()
+            .map(|(old, category)| (*old, category.category_id))
+            .collect();
+        assert_eq!(ids, vec![(1, 10), (2, 10), (3, 30)]);
+    }
+}
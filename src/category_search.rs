@@ -0,0 +1,302 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{category_tree::CategoryTree, product_category::NewCategory};
+
+/// Which name of a category matched a search.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchedName {
+    English,
+    Chinese,
+}
+
+/// A leaf category returned by `search_leaf_categories`, best matches first.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryMatch {
+    pub category_id: i32,
+    pub name: String,
+    pub cn_name: Option<String>,
+
+    /// The full path, e.g. `Machinery > Agriculture > Tractors`.
+    pub path: String,
+
+    /// Higher is better; an exact match scores 100.
+    pub score: u32,
+    pub matched: MatchedName,
+}
+
+impl CategoryTree {
+    /// Resolves the categories from the top level down to `category_id`.
+    ///
+    /// The parent links recorded while crawling are followed first. When they stop
+    /// short of a top-level category, e.g. in a crawl rooted below the top level,
+    /// the category's own `parent_ids` are used instead, provided every ancestor
+    /// listed there is in the tree and the first of them is a top-level category.
+    ///
+    /// # Arguments
+    ///
+    /// * `category_id` - The category to resolve, e.g. the one a product belongs to.
+    ///
+    /// # Returns
+    ///
+    /// The categories along the path, ending with `category_id` itself, or an empty
+    /// vector if the category is not in the tree.
+    pub fn resolve_path(&self, category_id: i32) -> Vec<&NewCategory> {
+        let node = match self.get(category_id) {
+            Some(node) => node,
+            None => return vec![],
+        };
+
+        let path: Vec<&NewCategory> = self
+            .path(category_id)
+            .into_iter()
+            .map(|node| &node.category)
+            .filter(|category| category.level > 0)
+            .collect();
+        if path.first().map_or(false, |top| top.level == 1) {
+            return path;
+        }
+
+        if let Some(parent_ids) = &node.category.parent_ids {
+            let ancestors: Option<Vec<&NewCategory>> = parent_ids
+                .number
+                .iter()
+                .map(|id| {
+                    id.parse::<i32>()
+                        .ok()
+                        .and_then(|id| self.get(id))
+                        .map(|node| &node.category)
+                })
+                .collect();
+
+            if let Some(mut ancestors) = ancestors {
+                ancestors.retain(|ancestor| ancestor.category_id != category_id);
+                ancestors.sort_by_key(|ancestor| ancestor.level);
+                if ancestors.first().map_or(false, |top| top.level == 1) {
+                    ancestors.push(&node.category);
+                    return ancestors;
+                }
+            }
+        }
+
+        path
+    }
+
+    /// Returns the breadcrumb for `category_id`, e.g. `Machinery > Agriculture > Tractors`.
+    ///
+    /// # Arguments
+    ///
+    /// * `category_id` - The category to resolve.
+    /// * `separator` - The text placed between names, usually `" > "`.
+    ///
+    /// # Returns
+    ///
+    /// The English names joined with `separator`, or `None` if the category is not
+    /// in the tree.
+    pub fn breadcrumb(&self, category_id: i32, separator: &str) -> Option<String> {
+        let path = self.resolve_path(category_id);
+        if path.is_empty() {
+            return None;
+        }
+
+        Some(
+            path.iter()
+                .map(|category| category.name.as_str())
+                .collect::<Vec<_>>()
+                .join(separator),
+        )
+    }
+
+    /// Searches leaf categories by English or Chinese name.
+    ///
+    /// Matching is case-insensitive. Exact names rank first, then names starting
+    /// with the keyword, names containing a word starting with it, names containing
+    /// it anywhere, and finally names containing its characters in order (a fuzzy
+    /// match, ranked by how tightly they are packed).
+    ///
+    /// # Arguments
+    ///
+    /// * `keyword` - The text to look for.
+    /// * `limit` - The maximum number of results.
+    ///
+    /// # Returns
+    ///
+    /// The matching leaf categories, best first.
+    pub fn search_leaf_categories(&self, keyword: &str, limit: usize) -> Vec<CategoryMatch> {
+        let keyword = keyword.trim().to_lowercase();
+        if keyword.is_empty() {
+            return vec![];
+        }
+
+        let mut matches = Vec::new();
+        for node in self.nodes.values() {
+            let category = &node.category;
+            if !category.leaf_category {
+                continue;
+            }
+
+            let english = score(&category.name.to_lowercase(), &keyword)
+                .map(|score| (score, MatchedName::English));
+            let chinese = category
+                .cn_name
+                .as_ref()
+                .and_then(|cn_name| score(&cn_name.to_lowercase(), &keyword))
+                .map(|score| (score, MatchedName::Chinese));
+
+            let (score, matched) = match (english, chinese) {
+                (Some(en), Some(cn)) if cn.0 > en.0 => cn,
+                (Some(en), _) => en,
+                (None, Some(cn)) => cn,
+                (None, None) => continue,
+            };
+
+            matches.push(CategoryMatch {
+                category_id: category.category_id,
+                name: category.name.clone(),
+                cn_name: category.cn_name.clone(),
+                path: self
+                    .breadcrumb(category.category_id, " > ")
+                    .unwrap_or_default(),
+                score,
+                matched,
+            });
+        }
+
+        matches.sort_by_key(|m| (Reverse(m.score), m.name.len(), m.category_id));
+        matches.truncate(limit);
+        matches
+    }
+}
+
+/// Scores how well `name` matches `keyword`, both lowercase.
+fn score(name: &str, keyword: &str) -> Option<u32> {
+    if name == keyword {
+        return Some(100);
+    }
+    if name.starts_with(keyword) {
+        return Some(90);
+    }
+    if name
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| word.starts_with(keyword))
+    {
+        return Some(80);
+    }
+    if name.contains(keyword) {
+        return Some(70);
+    }
+
+    // Fuzzy: every keyword character appears in order. The fewer characters
+    // skipped in between, the higher the score, up to 60; scattered matches
+    // below 20 are noise.
+    let mut chars = name.chars();
+    let mut skipped = 0;
+    for wanted in keyword.chars() {
+        loop {
+            match chars.next() {
+                Some(c) if c == wanted => break,
+                Some(_) => skipped += 1,
+                None => return None,
+            }
+        }
+    }
+
+    let length = keyword.chars().count() as u32;
+    Some(60 * length / (length + skipped)).filter(|score| *score >= 20)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn category(
+        category_id: i32,
+        level: i32,
+        name: &str,
+        children: &[i32],
+        parents: &[i32],
+    ) -> NewCategory {
+        let list = |ids: &[i32]| {
+            if ids.is_empty() {
+                json!({})
+            } else {
+                json!({ "number": ids.iter().map(|id| id.to_string()).collect::<Vec<_>>() })
+            }
+        };
+        serde_json::from_value(json!({
+            "leaf_category": children.is_empty(),
+            "category_id": category_id,
+            "level": level,
+            "name": name,
+            "child_ids": list(children),
+            "parent_ids": list(parents),
+        }))
+        .unwrap()
+    }
+
+    fn names(path: Vec<&NewCategory>) -> Vec<&str> {
+        path.into_iter()
+            .map(|category| category.name.as_str())
+            .collect()
+    }
+
+    /// Machinery (1) > Agriculture (2) > Tractors (3), crawled from the virtual root 0.
+    fn tree(tractor_parents: &[i32]) -> CategoryTree {
+        let mut tree = CategoryTree::new(category(0, 0, "Root", &[1], &[]));
+        tree.insert(category(1, 1, "Machinery", &[2], &[]), Some(0), 1);
+        tree.insert(category(2, 2, "Agriculture", &[3], &[1]), Some(1), 2);
+        tree.insert(category(3, 3, "Tractors", &[], tractor_parents), Some(2), 3);
+        tree
+    }
+
+    #[test]
+    fn resolve_path_follows_parent_links() {
+        // Only the direct parent is listed, as some responses do.
+        let tree = tree(&[2]);
+        assert_eq!(
+            names(tree.resolve_path(3)),
+            vec!["Machinery", "Agriculture", "Tractors"]
+        );
+        assert_eq!(
+            tree.breadcrumb(3, " > ").as_deref(),
+            Some("Machinery > Agriculture > Tractors")
+        );
+        assert!(tree.resolve_path(42).is_empty());
+        assert_eq!(tree.breadcrumb(42, " > "), None);
+    }
+
+    #[test]
+    fn resolve_path_falls_back_to_parent_ids_for_partial_crawls() {
+        // A crawl rooted at Agriculture that also fetched Machinery out of band.
+        let mut tree = CategoryTree::new(category(2, 2, "Agriculture", &[3], &[1]));
+        tree.insert(category(3, 3, "Tractors", &[], &[1, 2]), Some(2), 1);
+        tree.insert(category(1, 1, "Machinery", &[2], &[]), None, 0);
+
+        assert_eq!(
+            names(tree.resolve_path(3)),
+            vec!["Machinery", "Agriculture", "Tractors"]
+        );
+    }
+
+    #[test]
+    fn resolve_path_ignores_parent_ids_not_reaching_the_top() {
+        let mut tree = CategoryTree::new(category(2, 2, "Agriculture", &[3], &[1]));
+        tree.insert(category(3, 3, "Tractors", &[], &[2]), Some(2), 1);
+
+        assert_eq!(names(tree.resolve_path(3)), vec!["Agriculture", "Tractors"]);
+    }
+
+    #[test]
+    fn search_ranks_exact_matches_first() {
+        let mut tree = tree(&[1, 2]);
+        tree.insert(category(4, 3, "Tractor Parts", &[], &[1, 2]), Some(2), 3);
+
+        let matches = tree.search_leaf_categories("tractors", 10);
+        assert_eq!(matches[0].category_id, 3);
+        assert_eq!(matches[0].score, 100);
+        assert_eq!(matches[0].path, "Machinery > Agriculture > Tractors");
+        assert_eq!(matches[0].matched, MatchedName::English);
+    }
+}
@@ -0,0 +1,436 @@
+use std::{
+    collections::{BTreeMap, HashSet, VecDeque},
+    fmt,
+    future::Future,
+};
+
+use futures::stream::{FuturesUnordered, StreamExt};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{product_category::NewCategory, IopClient};
+
+/// A category fetched while crawling, linked to its parent and children.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryNode {
+    pub category: NewCategory,
+
+    /// The category this one was reached from, `None` for the root of the crawl.
+    pub parent_id: Option<i32>,
+
+    /// The child category IDs, parsed from `category.child_ids`.
+    pub children: Vec<i32>,
+
+    /// The distance from the root of the crawl.
+    pub depth: u32,
+}
+
+/// An owned category tree, keyed by category ID.
+///
+/// A tree may be partial: children listed by a node are not necessarily present
+/// yet. Such a snapshot can be serialized and handed back to
+/// `resume_category_crawl` to finish the crawl.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryTree {
+    pub root_id: i32,
+    pub nodes: BTreeMap<i32, CategoryNode>,
+}
+
+impl CategoryTree {
+    /// Creates a tree containing only the root category.
+    pub fn new(root: NewCategory) -> Self {
+        let mut tree = CategoryTree {
+            root_id: root.category_id,
+            nodes: BTreeMap::new(),
+        };
+        tree.insert(root, None, 0);
+        tree
+    }
+
+    /// Adds a category below `parent_id`, replacing any previous copy.
+    pub fn insert(&mut self, category: NewCategory, parent_id: Option<i32>, depth: u32) {
+        let children = child_ids(&category);
+        self.nodes.insert(
+            category.category_id,
+            CategoryNode {
+                category,
+                parent_id,
+                children,
+                depth,
+            },
+        );
+    }
+
+    /// Returns the root node, if it has been fetched.
+    pub fn root(&self) -> Option<&CategoryNode> {
+        self.nodes.get(&self.root_id)
+    }
+
+    /// Returns the node for `category_id`, if it has been fetched.
+    pub fn get(&self, category_id: i32) -> Option<&CategoryNode> {
+        self.nodes.get(&category_id)
+    }
+
+    /// Returns the parent node of `category_id`, if both have been fetched.
+    pub fn parent(&self, category_id: i32) -> Option<&CategoryNode> {
+        self.get(category_id)
+            .and_then(|node| node.parent_id)
+            .and_then(|parent_id| self.get(parent_id))
+    }
+
+    /// Returns the fetched children of `category_id`.
+    pub fn children(&self, category_id: i32) -> Vec<&CategoryNode> {
+        match self.get(category_id) {
+            Some(node) => node
+                .children
+                .iter()
+                .filter_map(|id| self.get(*id))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Returns the nodes from the root down to `category_id`, following the
+    /// parent links recorded while crawling.
+    ///
+    /// The path stops early if an ancestor is missing from the tree.
+    pub fn path(&self, category_id: i32) -> Vec<&CategoryNode> {
+        let mut path = Vec::new();
+        let mut current = self.get(category_id);
+        while let Some(node) = current {
+            // Guards against cycles in hand-edited snapshots.
+            if path.len() > self.len() {
+                break;
+            }
+            path.push(node);
+            current = node.parent_id.and_then(|parent_id| self.get(parent_id));
+        }
+        path.reverse();
+        path
+    }
+
+    /// Returns the number of fetched categories.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Returns `true` if no category has been fetched.
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Lists the categories still to be fetched to reach `max_depth`, as
+    /// `(category_id, parent_id, depth)`.
+    ///
+    /// A category listed by several parents appears once, below the first of them.
+    pub fn pending(&self, max_depth: u32) -> Vec<(i32, i32, u32)> {
+        let mut enqueued = HashSet::new();
+        let mut pending = Vec::new();
+        for id in self.nodes.keys() {
+            pending.extend(self.unfetched_children(*id, max_depth, &mut enqueued));
+        }
+        pending
+    }
+
+    /// Lists the children of `category_id` that are neither fetched nor already
+    /// in `enqueued`, recording them there.
+    fn unfetched_children(
+        &self,
+        category_id: i32,
+        max_depth: u32,
+        enqueued: &mut HashSet<i32>,
+    ) -> Vec<(i32, i32, u32)> {
+        let node = match self.get(category_id) {
+            Some(node) if node.depth < max_depth => node,
+            _ => return vec![],
+        };
+
+        node.children
+            .iter()
+            .filter(|child_id| !self.nodes.contains_key(child_id) && enqueued.insert(**child_id))
+            .map(|child_id| (*child_id, category_id, node.depth + 1))
+            .collect()
+    }
+
+    /// Returns `true` if every category down to `max_depth` has been fetched.
+    pub fn is_complete(&self, max_depth: u32) -> bool {
+        self.root().is_some() && self.pending(max_depth).is_empty()
+    }
+}
+
+fn child_ids(category: &NewCategory) -> Vec<i32> {
+    let child_ids = match &category.child_ids {
+        Some(child_ids) => child_ids,
+        None => return vec![],
+    };
+
+    let mut ids = Vec::new();
+    for id in &child_ids.number {
+        match id.parse::<i32>() {
+            Ok(id) => ids.push(id),
+            Err(_) => warn!("Invalid child category id: {:?}", id),
+        }
+    }
+    ids
+}
+
+/// Returned when a crawl stops before completion.
+///
+/// `snapshot` holds every category fetched so far and can be passed to
+/// `resume_category_crawl` once the cause has been dealt with.
+#[derive(Debug)]
+pub struct CategoryCrawlError {
+    pub snapshot: Option<CategoryTree>,
+    pub category_id: i32,
+    pub message: String,
+}
+
+impl fmt::Display for CategoryCrawlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Failed to crawl category {}: {}",
+            self.category_id, self.message
+        )
+    }
+}
+
+impl std::error::Error for CategoryCrawlError {}
+
+impl IopClient {
+    /// Crawls the ICBU category tree below `root`.
+    ///
+    /// Walks `child_ids` breadth-first through `list_product_categories`, keeping at
+    /// most `concurrency` requests in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The category ID to start from, `0` for the whole tree.
+    /// * `max_depth` - How many levels below `root` to fetch; `0` fetches `root` only.
+    /// * `concurrency` - The maximum number of concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `CategoryTree` if successful, or a `CategoryCrawlError`
+    /// carrying the partial snapshot if a request fails.
+    pub async fn crawl_category_tree(
+        &self,
+        root: i32,
+        max_depth: u32,
+        concurrency: usize,
+    ) -> Result<CategoryTree, CategoryCrawlError> {
+        let category = match self.list_product_categories(root).await {
+            Ok(category) => category,
+            Err(err) => {
+                return Err(CategoryCrawlError {
+                    snapshot: None,
+                    category_id: root,
+                    message: err.to_string(),
+                })
+            }
+        };
+
+        self.resume_category_crawl(CategoryTree::new(category), max_depth, concurrency)
+            .await
+    }
+
+    /// Resumes a crawl from a partial snapshot, fetching only the missing categories.
+    ///
+    /// # Arguments
+    ///
+    /// * `snapshot` - A tree returned by an interrupted crawl.
+    /// * `max_depth` - How many levels below the root to fetch.
+    /// * `concurrency` - The maximum number of concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the completed `CategoryTree` if successful, or a
+    /// `CategoryCrawlError` carrying the extended snapshot if a request fails.
+    pub async fn resume_category_crawl(
+        &self,
+        snapshot: CategoryTree,
+        max_depth: u32,
+        concurrency: usize,
+    ) -> Result<CategoryTree, CategoryCrawlError> {
+        let tree = crawl(snapshot, max_depth, concurrency, |id| async move {
+            self.list_product_categories(id)
+                .await
+                .map_err(|err| err.to_string())
+        })
+        .await?;
+
+        info!(
+            "--------crawl_category_tree-------- root: {}, categories: {}",
+            tree.root_id,
+            tree.len()
+        );
+
+        Ok(tree)
+    }
+}
+
+/// Fetches the pending categories of `tree` through `fetch`, keeping at most
+/// `concurrency` requests in flight and fetching each category once.
+async fn crawl<F, Fut>(
+    mut tree: CategoryTree,
+    max_depth: u32,
+    concurrency: usize,
+    fetch: F,
+) -> Result<CategoryTree, CategoryCrawlError>
+where
+    F: Fn(i32) -> Fut,
+    Fut: Future<Output = Result<NewCategory, String>>,
+{
+    let mut queue: VecDeque<_> = tree.pending(max_depth).into();
+    let mut enqueued: HashSet<i32> = queue.iter().map(|(id, _, _)| *id).collect();
+    let mut in_flight = FuturesUnordered::new();
+    let concurrency = concurrency.max(1);
+
+    loop {
+        while in_flight.len() < concurrency {
+            let (id, parent_id, depth) = match queue.pop_front() {
+                Some(next) => next,
+                None => break,
+            };
+            let request = fetch(id);
+            in_flight.push(async move { (id, parent_id, depth, request.await) });
+        }
+
+        let (id, parent_id, depth, result) = match in_flight.next().await {
+            Some(done) => done,
+            None => break,
+        };
+
+        let category = match result {
+            Ok(category) => category,
+            Err(message) => {
+                return Err(CategoryCrawlError {
+                    snapshot: Some(tree),
+                    category_id: id,
+                    message,
+                })
+            }
+        };
+
+        tree.insert(category, Some(parent_id), depth);
+        queue.extend(tree.unfetched_children(id, max_depth, &mut enqueued));
+    }
+
+    Ok(tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::{collections::HashMap, sync::Mutex};
+
+    fn category(category_id: i32, children: &[i32]) -> NewCategory {
+        let child_ids: Vec<String> = children.iter().map(|id| id.to_string()).collect();
+        serde_json::from_value(json!({
+            "leaf_category": children.is_empty(),
+            "category_id": category_id,
+            "level": 1,
+            "name": format!("Category {}", category_id),
+            "child_ids": if children.is_empty() { json!({}) } else { json!({ "number": child_ids }) },
+        }))
+        .unwrap()
+    }
+
+    /// 1 -> {2, 3}, 2 -> {4}, 3 -> {4, 5}: category 4 is reachable twice.
+    fn catalog() -> HashMap<i32, NewCategory> {
+        vec![
+            category(1, &[2, 3]),
+            category(2, &[4]),
+            category(3, &[4, 5]),
+            category(4, &[]),
+            category(5, &[]),
+        ]
+        .into_iter()
+        .map(|category| (category.category_id, category))
+        .collect()
+    }
+
+    async fn run(
+        tree: CategoryTree,
+        max_depth: u32,
+        failing: Option<i32>,
+        calls: &Mutex<Vec<i32>>,
+    ) -> Result<CategoryTree, CategoryCrawlError> {
+        let catalog = catalog();
+        crawl(tree, max_depth, 2, |id| {
+            calls.lock().unwrap().push(id);
+            let result = match catalog.get(&id) {
+                Some(category) if failing != Some(id) => Ok(category.clone()),
+                _ => Err(format!("no category {}", id)),
+            };
+            async move { result }
+        })
+        .await
+    }
+
+    #[test]
+    fn pending_lists_shared_children_once() {
+        let catalog = catalog();
+        let mut tree = CategoryTree::new(catalog[&1].clone());
+        tree.insert(catalog[&2].clone(), Some(1), 1);
+        tree.insert(catalog[&3].clone(), Some(1), 1);
+
+        assert_eq!(tree.pending(2), vec![(4, 2, 2), (5, 3, 2)]);
+        assert!(tree.pending(1).is_empty());
+        assert!(tree.is_complete(1));
+        assert!(!tree.is_complete(2));
+    }
+
+    #[tokio::test]
+    async fn crawl_fetches_shared_children_once() {
+        let calls = Mutex::new(Vec::new());
+        let tree = run(CategoryTree::new(catalog()[&1].clone()), 5, None, &calls)
+            .await
+            .unwrap();
+
+        let mut calls = calls.into_inner().unwrap();
+        calls.sort_unstable();
+        assert_eq!(calls, vec![2, 3, 4, 5]);
+        assert_eq!(tree.len(), 5);
+        assert!(tree.is_complete(5));
+        assert_eq!(tree.get(4).unwrap().depth, 2);
+    }
+
+    #[tokio::test]
+    async fn crawl_stops_at_max_depth() {
+        let calls = Mutex::new(Vec::new());
+        let tree = run(CategoryTree::new(catalog()[&1].clone()), 1, None, &calls)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            tree.nodes.keys().copied().collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+    }
+
+    #[tokio::test]
+    async fn resume_fetches_only_missing_categories() {
+        let calls = Mutex::new(Vec::new());
+        let err = run(CategoryTree::new(catalog()[&1].clone()), 5, Some(5), &calls)
+            .await
+            .unwrap_err();
+        assert_eq!(err.category_id, 5);
+        let snapshot = err.snapshot.unwrap();
+        assert!(!snapshot.nodes.contains_key(&5));
+
+        let fetched: HashSet<i32> = snapshot.nodes.keys().copied().collect();
+        let pending: Vec<i32> = snapshot.pending(5).iter().map(|(id, _, _)| *id).collect();
+        assert!(pending.contains(&5));
+
+        let calls = Mutex::new(Vec::new());
+        let tree = run(snapshot, 5, None, &calls).await.unwrap();
+
+        let calls = calls.into_inner().unwrap();
+        assert!(calls.iter().all(|id| !fetched.contains(id)));
+        assert_eq!(calls.iter().filter(|id| **id == 5).count(), 1);
+        assert!(tree.is_complete(5));
+        assert_eq!(tree.len(), 5);
+    }
+}
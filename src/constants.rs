@@ -9,9 +9,24 @@ pub mod methods {
     pub const AUTH_TOKEN_CREATE: &str = "/auth/token/create";
     pub const AUTH_TOKEN_REFRESH: &str = "/auth/token/refresh";
     pub const ALIBABA_ICBU_PRODUCT_GROUP_GET: &str = "alibaba.icbu.product.group.get";
+    pub const ALIBABA_ICBU_PRODUCT_GROUP_ADD: &str = "alibaba.icbu.product.group.add";
+    pub const ALIBABA_ICBU_PRODUCT_GROUP_UPDATE: &str = "alibaba.icbu.product.group.update";
+    pub const ALIBABA_ICBU_PRODUCT_GROUP_DELETE: &str = "alibaba.icbu.product.group.delete";
     pub const ALIBABA_ICBU_PHOTOBANK_GROUP_LIST: &str = "alibaba.icbu.photobank.group.list";
+    pub const ALIBABA_ICBU_PHOTOBANK_UPLOAD: &str = "alibaba.icbu.photobank.upload";
+    pub const ALIBABA_ICBU_PHOTOBANK_LIST: &str = "alibaba.icbu.photobank.list";
+    pub const ALIBABA_ICBU_PHOTOBANK_DELETE: &str = "alibaba.icbu.photobank.delete";
+    pub const ALIBABA_ICBU_PHOTOBANK_CAPACITY_GET: &str = "alibaba.icbu.photobank.capacity.get";
+    pub const ALIBABA_ICBU_PHOTOBANK_GROUP_ADD: &str = "alibaba.icbu.photobank.group.add";
+    pub const ALIBABA_ICBU_PHOTOBANK_GROUP_UPDATE: &str = "alibaba.icbu.photobank.group.update";
+    pub const ALIBABA_ICBU_PHOTOBANK_GROUP_DELETE: &str = "alibaba.icbu.photobank.group.delete";
     pub const ALIBABA_ICBU_CATEGORY_GET_NEW: &str = "alibaba.icbu.category.get.new";
     pub const ALIBABA_ICBU_CATEGORY_ATTRIBUTE_GET: &str = "alibaba.icbu.category.attribute.get";
+    pub const ALIBABA_ICBU_CATEGORY_ID_MAPPING: &str = "alibaba.icbu.category.id.mapping";
+    pub const ALIBABA_ICBU_CATEGORY_LEVEL_ATTR_GET: &str = "alibaba.icbu.category.level.attr.get";
+    pub const ALIBABA_ICBU_PRODUCT_LIST: &str = "alibaba.icbu.product.list";
+    pub const ALIBABA_ICBU_PRODUCT_GET: &str = "alibaba.icbu.product.get";
+    pub const ALIBABA_ICBU_PRODUCT_ADD: &str = "alibaba.icbu.product.add";
     pub const ALIBABA_ICBU_PRODUCT_COUNTRY_GETCOUNTRYLIST: &str =
         "alibaba.icbu.product.country.getcountrylist";
 }
@@ -20,12 +35,35 @@ pub mod methods {
 ///
 /// seconds
 pub mod caches {
+    pub const ONE_MINUTE_IN_SECONDS: u64 = 60;
     pub const FIVE_MINUTE_IN_SECONDS: u64 = 300;
     // pub const ONE_HOUR_IN_SECONDS: u64 = 3600;
     // pub const HALF_DAY_IN_SECONDS: u64 = 43200;
-    // pub const ONE_DAY_IN_SECONDS: u64 = 86400;
+    pub const ONE_DAY_IN_SECONDS: u64 = 86400;
+}
+
+/// ICBU limits checked locally before sending a request
+pub mod limits {
+    pub const PRODUCT_GROUP_NAME_MAX_CHARS: usize = 30;
+    pub const PRODUCT_GROUP_MAX_DEPTH: usize = 3;
+    pub const PHOTOBANK_LIST_PAGE_SIZE: u32 = 30;
+    pub const PHOTOBANK_GROUP_NAME_MAX_CHARS: usize = 20;
+    pub const PHOTOBANK_DELETE_BATCH_SIZE: usize = 20;
+    pub const PHOTOBANK_CAPACITY_WARNING_RATIO: f64 = 0.9;
+    pub const PRODUCT_LIST_PAGE_SIZE: u32 = 30;
+    pub const PRODUCT_SUBJECT_MAX_CHARS: usize = 128;
+    pub const PRODUCT_IMAGES_MAX: usize = 6;
+    pub const PRODUCT_KEYWORDS_MAX: usize = 3;
+    #[cfg(feature = "image")]
+    pub const PHOTOBANK_IMAGE_MAX_BYTES: usize = 5 * 1024 * 1024;
+    #[cfg(feature = "image")]
+    pub const PHOTOBANK_IMAGE_MAX_DIMENSION: u32 = 5000;
+    #[cfg(feature = "image")]
+    pub const PHOTOBANK_IMAGE_MIN_DIMENSION: u32 = 350;
 }
 
 pub mod keys {
     pub const ACCESS_TOKEN: &str = "iop:client:access_token";
+    pub const ACCESS_TOKEN_INVALIDATE: &str = "iop:client:access_token:invalidate";
+    pub const CATEGORY_ID_MAPPING: &str = "iop:client:category_id_mapping";
 }
@@ -1,11 +1,9 @@
+use bytes::Bytes;
 use chrono::Utc;
-use hmac::{Hmac, Mac};
-use sha2::Sha256;
+use hmac::Mac;
 use std::collections::HashMap;
 
-use crate::IopClient;
-
-type HmacSha256 = Hmac<Sha256>;
+use crate::{signed_params::SignedParams, IopClient};
 
 impl IopClient {
     /// Construct a URL with the given base URL, query parameters, and signature.
@@ -25,7 +23,7 @@ impl IopClient {
         params: HashMap<String, String>,
         sign: String,
     ) -> String {
-        let mut url = String::from(base_url);
+        let mut url = base_url;
         let mut first = true;
 
         for (key, value) in params {
@@ -53,9 +51,9 @@ impl IopClient {
     /// # Arguments
     ///
     /// * `method` - An optional string representing the HTTP method to be included
-    ///              in the signature calculation.
+    ///   in the signature calculation.
     /// * `payload` - A `HashMap` containing the parameters to be signed, where
-    ///               keys are parameter names and values are parameter values.
+    ///   keys are parameter names and values are parameter values.
     ///
     /// # Returns
     ///
@@ -65,19 +63,12 @@ impl IopClient {
         method: Option<String>,
         payload: HashMap<String, String>,
     ) -> String {
-        let mut sorted_vec: Vec<(&String, &String)> = payload.iter().collect();
-        sorted_vec.sort_by(|a, b| a.0.cmp(b.0));
-
-        let mut concatenated = match method {
-            Some(m) => String::from(m),
-            None => String::new(),
-        };
-        for (key, value) in sorted_vec {
-            concatenated.push_str(key);
-            concatenated.push_str(value);
-        }
+        let params: SignedParams = payload
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
 
-        self.generate_hmac_sha256(concatenated.as_bytes())
+        params.sign(&self.signing_key, method.as_deref())
     }
 
     /// Generates an HMAC-SHA256 signature of the provided data.
@@ -95,9 +86,7 @@ impl IopClient {
     ///
     /// A `String` representing the computed HMAC-SHA256 signature in hexadecimal format.
     pub fn generate_hmac_sha256(&self, data: &[u8]) -> String {
-        let app_secret = self.app_secret.clone();
-        let mut mac = HmacSha256::new_from_slice(app_secret.as_bytes())
-            .expect("HMAC can take key of any size");
+        let mut mac = self.signing_key.mac();
 
         mac.update(data);
         let result = mac.finalize();
@@ -116,7 +105,7 @@ impl IopClient {
     /// # Arguments
     ///
     /// * `params` - A `HashMap` containing business-specific parameters to be included
-    ///              in the API request.
+    ///   in the API request.
     ///
     /// # Returns
     ///
@@ -124,18 +113,12 @@ impl IopClient {
         &self,
         params: HashMap<String, String>,
     ) -> HashMap<String, String> {
-        let now = Utc::now().timestamp_millis().to_string();
-
-        let mut map = HashMap::new();
-        map.insert("app_key".to_string(), self.appid.to_string());
-        map.insert("sign_method".to_string(), "sha256".to_string());
-        map.insert("simplify".to_string(), "true".to_string());
-        map.insert("timestamp".to_string(), now);
-        map.insert("language".to_string(), "en_US".to_string());
-
-        if let Ok(at) = self.get_access_token().await {
-            map.insert("access_token".to_string(), at.access_token.clone());
-        }
+        let mut map: HashMap<String, String> = self
+            .build_signed_params()
+            .await
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
 
         // 业务参数
         for (key, value) in params {
@@ -144,4 +127,64 @@ impl IopClient {
 
         map
     }
+
+    /// Builds the common request parameters as a `SignedParams`.
+    ///
+    /// Includes `app_key`, `timestamp`, `sign_method`, `simplify` and language, plus the
+    /// `access_token` if available. The app key is borrowed from the client, so no
+    /// parameter is copied until the URL is serialized. Business parameters are
+    /// inserted by the caller afterwards and take precedence.
+    ///
+    /// # Returns
+    ///
+    /// A `SignedParams` holding the common parameters.
+    pub async fn build_signed_params(&self) -> SignedParams<'_> {
+        let mut params = SignedParams::with_capacity(10);
+        params.insert("app_key", self.appid.as_str());
+        params.insert("sign_method", "sha256");
+        params.insert("simplify", "true");
+        params.insert("timestamp", Utc::now().timestamp_millis().to_string());
+        params.insert("language", "en_US");
+
+        if let Ok(at) = self.get_access_token().await {
+            params.insert("access_token", at.access_token);
+        }
+
+        params
+    }
+
+    /// Sends a read request to the gateway, coalescing it with identical requests
+    /// that are already in flight.
+    ///
+    /// Two requests are identical when every signed parameter except `timestamp`
+    /// matches, i.e. the same method, business parameters, seller (`access_token`)
+    /// and language. Only the first of them reaches the gateway; the others wait
+    /// for its response body.
+    ///
+    /// # Arguments
+    ///
+    /// * `params` - The complete request parameters, as returned by `build_signed_params`.
+    /// * `url` - The signed request URL.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the raw response body if successful, or an error if the
+    /// request fails.
+    pub(crate) async fn send_deduplicated(
+        &self,
+        params: &SignedParams<'_>,
+        url: String,
+    ) -> Result<Bytes, Box<dyn std::error::Error>> {
+        self.inflight
+            .run(params.dedup_key(), || async {
+                let response = self
+                    .client
+                    .get(&url)
+                    .send()
+                    .await
+                    .map_err(|err| err.to_string())?;
+                response.bytes().await.map_err(|err| err.to_string())
+            })
+            .await
+    }
 }
@@ -0,0 +1,660 @@
+use std::{fmt, io::Cursor, path::Path};
+
+use bytes::Bytes;
+use image::{
+    codecs::jpeg::JpegEncoder, imageops::FilterType, DynamicImage, GenericImageView, ImageDecoder,
+    ImageFormat, ImageReader, Rgb, RgbImage, Rgba, RgbaImage,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{constants::limits, model, IopClient};
+
+/// JPEG qualities tried, in order, to fit an image under the size limit.
+const JPEG_QUALITIES: [u8; 5] = [90, 80, 70, 60, 50];
+
+/// An encoding accepted by the photo bank.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhotobankFormat {
+    Jpeg,
+    Png,
+}
+
+impl PhotobankFormat {
+    fn from_image_format(format: ImageFormat) -> Option<Self> {
+        match format {
+            ImageFormat::Jpeg => Some(PhotobankFormat::Jpeg),
+            ImageFormat::Png => Some(PhotobankFormat::Png),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            PhotobankFormat::Jpeg => "jpg",
+            PhotobankFormat::Png => "png",
+        }
+    }
+}
+
+/// A change made to an image by `ImagePipeline::process`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ImageChange {
+    Resized {
+        from: (u32, u32),
+        to: (u32, u32),
+    },
+    PaddedToSquare {
+        from: (u32, u32),
+        to: (u32, u32),
+    },
+    ReEncoded {
+        from: String,
+        to: PhotobankFormat,
+    },
+    MetadataStripped,
+    /// The JPEG quality was lowered to fit the size limit.
+    Recompressed {
+        quality: u8,
+    },
+}
+
+impl fmt::Display for ImageChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageChange::Resized { from, to } => {
+                write!(f, "Resized from {}x{} to {}x{}", from.0, from.1, to.0, to.1)
+            }
+            ImageChange::PaddedToSquare { from, to } => {
+                write!(f, "Padded from {}x{} to {}x{}", from.0, from.1, to.0, to.1)
+            }
+            ImageChange::ReEncoded { from, to } => write!(f, "Re-encoded from {from} to {to:?}"),
+            ImageChange::MetadataStripped => write!(f, "Stripped EXIF metadata"),
+            ImageChange::Recompressed { quality } => {
+                write!(f, "Recompressed at JPEG quality {quality}")
+            }
+        }
+    }
+}
+
+/// A photo bank constraint an image does not meet.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ImageViolation {
+    /// The encoding is not accepted by the photo bank.
+    UnsupportedFormat {
+        format: String,
+    },
+    TooLarge {
+        bytes: usize,
+        max: usize,
+    },
+    TooBig {
+        width: u32,
+        height: u32,
+        max: u32,
+    },
+    TooSmall {
+        width: u32,
+        height: u32,
+        min: u32,
+    },
+}
+
+impl fmt::Display for ImageViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageViolation::UnsupportedFormat { format } => {
+                write!(f, "Format {format} is not accepted by the photo bank")
+            }
+            ImageViolation::TooLarge { bytes, max } => {
+                write!(f, "Image is {bytes} bytes, at most {max} are accepted")
+            }
+            ImageViolation::TooBig { width, height, max } => {
+                write!(
+                    f,
+                    "Image is {width}x{height}, sides of at most {max} are accepted"
+                )
+            }
+            ImageViolation::TooSmall { width, height, min } => {
+                write!(
+                    f,
+                    "Image is {width}x{height}, sides of at least {min} are required"
+                )
+            }
+        }
+    }
+}
+
+/// An image ready for `upload_photo_bank_image`.
+#[derive(Debug, Clone)]
+pub struct ProcessedImage {
+    /// The file name, with the extension matching the output encoding.
+    pub file_name: String,
+    pub bytes: Bytes,
+    pub width: u32,
+    pub height: u32,
+
+    /// What was changed, empty if the original bytes are returned.
+    pub changes: Vec<ImageChange>,
+}
+
+/// Validates images against the photo bank constraints and optionally fixes them.
+///
+/// Only validation is enabled by default; each fix is turned on with its builder method.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImagePipeline {
+    max_bytes: usize,
+    max_dimension: u32,
+    min_dimension: u32,
+    resize: bool,
+    format: Option<PhotobankFormat>,
+    strip_metadata: bool,
+    pad_to_square: bool,
+}
+
+impl Default for ImagePipeline {
+    fn default() -> Self {
+        ImagePipeline {
+            max_bytes: limits::PHOTOBANK_IMAGE_MAX_BYTES,
+            max_dimension: limits::PHOTOBANK_IMAGE_MAX_DIMENSION,
+            min_dimension: limits::PHOTOBANK_IMAGE_MIN_DIMENSION,
+            resize: false,
+            format: None,
+            strip_metadata: false,
+            pad_to_square: false,
+        }
+    }
+}
+
+impl ImagePipeline {
+    /// Creates a pipeline that only validates against the photo bank limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the size and dimension limits.
+    pub fn limits(mut self, max_bytes: usize, min_dimension: u32, max_dimension: u32) -> Self {
+        self.max_bytes = max_bytes;
+        self.min_dimension = min_dimension;
+        self.max_dimension = max_dimension;
+        self
+    }
+
+    /// Shrinks images whose longer side exceeds the maximum dimension.
+    pub fn resize(mut self) -> Self {
+        self.resize = true;
+        self
+    }
+
+    /// Re-encodes every image to `format`. Without it, images in an accepted format
+    /// keep it and others are converted to JPEG.
+    pub fn format(mut self, format: PhotobankFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+
+    /// Removes EXIF metadata, applying the EXIF orientation to the pixels first.
+    pub fn strip_metadata(mut self) -> Self {
+        self.strip_metadata = true;
+        self
+    }
+
+    /// Pads images to a square on a white background, as expected for main images.
+    pub fn pad_to_square(mut self) -> Self {
+        self.pad_to_square = true;
+        self
+    }
+
+    /// Checks an encoded image against the limits without changing it.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing every violation found, empty if the image can be uploaded
+    /// as is, or an error if the image cannot be decoded.
+    pub fn validate(
+        &self,
+        bytes: &[u8],
+    ) -> Result<Vec<ImageViolation>, Box<dyn std::error::Error>> {
+        let format = image::guess_format(bytes)?;
+        let (width, height) =
+            ImageReader::with_format(Cursor::new(bytes), format).into_dimensions()?;
+
+        let mut violations = self.check(bytes.len(), width, height);
+        if PhotobankFormat::from_image_format(format).is_none() {
+            violations.insert(
+                0,
+                ImageViolation::UnsupportedFormat {
+                    format: format!("{format:?}"),
+                },
+            );
+        }
+        Ok(violations)
+    }
+
+    /// Applies the enabled fixes, then checks the result against the limits.
+    ///
+    /// The original bytes are kept when nothing needs to change. Otherwise the image is
+    /// re-encoded, which drops all metadata; JPEG quality is lowered step by step if
+    /// the result is still over the size limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `file_name` - The original file name, used for the output name.
+    /// * `bytes` - The encoded image.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ProcessedImage` if it meets the limits, or an error
+    /// listing the remaining violations or why the image could not be processed.
+    pub fn process(
+        &self,
+        file_name: &str,
+        bytes: &[u8],
+    ) -> Result<ProcessedImage, Box<dyn std::error::Error>> {
+        let source_format = image::guess_format(bytes)?;
+        let accepted = PhotobankFormat::from_image_format(source_format);
+
+        let mut decoder =
+            ImageReader::with_format(Cursor::new(bytes), source_format).into_decoder()?;
+        let orientation = decoder.orientation()?;
+        let mut image = DynamicImage::from_decoder(decoder)?;
+        image.apply_orientation(orientation);
+
+        let mut changes = Vec::new();
+
+        let (width, height) = image.dimensions();
+        if self.resize && width.max(height) > self.max_dimension {
+            image = image.resize(self.max_dimension, self.max_dimension, FilterType::Lanczos3);
+            changes.push(ImageChange::Resized {
+                from: (width, height),
+                to: image.dimensions(),
+            });
+        }
+
+        let (width, height) = image.dimensions();
+        if self.pad_to_square && width != height {
+            image = pad_to_square(&image);
+            changes.push(ImageChange::PaddedToSquare {
+                from: (width, height),
+                to: image.dimensions(),
+            });
+        }
+
+        let format = self.format.or(accepted).unwrap_or(PhotobankFormat::Jpeg);
+        if accepted != Some(format) {
+            changes.push(ImageChange::ReEncoded {
+                from: format!("{source_format:?}"),
+                to: format,
+            });
+        }
+
+        if self.strip_metadata && has_exif(bytes) {
+            changes.push(ImageChange::MetadataStripped);
+        }
+
+        let (width, height) = image.dimensions();
+        if changes.is_empty() {
+            let violations = self.check(bytes.len(), width, height);
+            if !violations.is_empty() {
+                return Err(join_violations(&violations).into());
+            }
+            return Ok(ProcessedImage {
+                file_name: file_name.to_string(),
+                bytes: Bytes::copy_from_slice(bytes),
+                width,
+                height,
+                changes,
+            });
+        }
+
+        let encoded = match format {
+            PhotobankFormat::Png => {
+                let mut encoded = Vec::new();
+                image.write_to(&mut Cursor::new(&mut encoded), ImageFormat::Png)?;
+                encoded
+            }
+            PhotobankFormat::Jpeg => {
+                let rgb = DynamicImage::ImageRgb8(flatten_on_white(&image));
+                let mut encoded = Vec::new();
+                for (index, quality) in JPEG_QUALITIES.iter().enumerate() {
+                    encoded.clear();
+                    rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, *quality))?;
+                    if encoded.len() <= self.max_bytes {
+                        if index > 0 {
+                            changes.push(ImageChange::Recompressed { quality: *quality });
+                        }
+                        break;
+                    }
+                }
+                encoded
+            }
+        };
+
+        let violations = self.check(encoded.len(), width, height);
+        if !violations.is_empty() {
+            return Err(join_violations(&violations).into());
+        }
+
+        Ok(ProcessedImage {
+            file_name: with_extension(file_name, format.extension()),
+            bytes: Bytes::from(encoded),
+            width,
+            height,
+            changes,
+        })
+    }
+
+    fn check(&self, bytes: usize, width: u32, height: u32) -> Vec<ImageViolation> {
+        let mut violations = Vec::new();
+        if bytes > self.max_bytes {
+            violations.push(ImageViolation::TooLarge {
+                bytes,
+                max: self.max_bytes,
+            });
+        }
+        if width.max(height) > self.max_dimension {
+            violations.push(ImageViolation::TooBig {
+                width,
+                height,
+                max: self.max_dimension,
+            });
+        }
+        if width.min(height) < self.min_dimension {
+            violations.push(ImageViolation::TooSmall {
+                width,
+                height,
+                min: self.min_dimension,
+            });
+        }
+        violations
+    }
+}
+
+impl IopClient {
+    /// Runs an image through `pipeline`, then uploads it to the photo bank.
+    ///
+    /// # Arguments
+    ///
+    /// * `pipeline` - The checks and fixes to apply.
+    /// * `group_id` - The `PhotoAlbumGroup.id` to upload into. If `None`, the image goes to the default group.
+    /// * `file_name` - The original file name.
+    /// * `bytes` - The encoded image.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the hosted `UploadedPhoto` and the changes made if
+    /// successful, or an error if the image does not meet the limits or the upload fails.
+    pub async fn upload_processed_photo_bank_image(
+        &self,
+        pipeline: &ImagePipeline,
+        group_id: Option<i32>,
+        file_name: &str,
+        bytes: &[u8],
+    ) -> Result<(model::UploadedPhoto, Vec<ImageChange>), Box<dyn std::error::Error>> {
+        let processed = pipeline.process(file_name, bytes)?;
+        let uploaded = self
+            .upload_photo_bank_image(group_id, &processed.file_name, processed.bytes)
+            .await?;
+
+        Ok((uploaded, processed.changes))
+    }
+}
+
+fn pad_to_square(image: &DynamicImage) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let side = width.max(height);
+
+    let mut canvas = RgbaImage::from_pixel(side, side, Rgba([255, 255, 255, 255]));
+    image::imageops::overlay(
+        &mut canvas,
+        &image.to_rgba8(),
+        ((side - width) / 2) as i64,
+        ((side - height) / 2) as i64,
+    );
+    DynamicImage::ImageRgba8(canvas)
+}
+
+/// Drops the alpha channel for JPEG, which has none, blending transparent pixels
+/// onto white rather than keeping whatever color they hide.
+fn flatten_on_white(image: &DynamicImage) -> RgbImage {
+    if !image.color().has_alpha() {
+        return image.to_rgb8();
+    }
+
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let Rgba([r, g, b, a]) = *rgba.get_pixel(x, y);
+        let blend =
+            |channel: u8| ((channel as u32 * a as u32 + 255 * (255 - a as u32) + 127) / 255) as u8;
+        Rgb([blend(r), blend(g), blend(b)])
+    })
+}
+
+/// Returns `true` if a JPEG or PNG carries an EXIF block.
+fn has_exif(bytes: &[u8]) -> bool {
+    bytes.windows(6).any(|window| window == b"Exif\0\0")
+        || bytes.windows(4).any(|window| window == b"eXIf")
+}
+
+fn with_extension(file_name: &str, extension: &str) -> String {
+    Path::new(file_name)
+        .with_extension(extension)
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn join_violations(violations: &[ImageViolation]) -> String {
+    violations
+        .iter()
+        .map(|violation| violation.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::codecs::png::PngEncoder;
+
+    /// A pipeline whose limits suit small test images.
+    fn pipeline() -> ImagePipeline {
+        ImagePipeline::new().limits(1024 * 1024, 10, 200)
+    }
+
+    fn png(image: &DynamicImage) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        image
+            .write_with_encoder(PngEncoder::new(&mut encoded))
+            .unwrap();
+        encoded
+    }
+
+    fn jpeg(image: &DynamicImage, quality: u8) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        image
+            .to_rgb8()
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, quality))
+            .unwrap();
+        encoded
+    }
+
+    fn solid(width: u32, height: u32) -> DynamicImage {
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(width, height, Rgb([20, 40, 200])))
+    }
+
+    /// Pseudo-random pixels, which JPEG compresses poorly at high quality.
+    fn noise(width: u32, height: u32) -> DynamicImage {
+        let mut state: u32 = 12345;
+        DynamicImage::ImageRgb8(RgbImage::from_fn(width, height, |_, _| {
+            let mut next = || {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            };
+            Rgb([next(), next(), next()])
+        }))
+    }
+
+    /// Inserts a minimal EXIF segment right after the JPEG start marker.
+    fn with_exif(jpeg: &[u8]) -> Vec<u8> {
+        let tiff = b"II*\0\x08\0\0\0\0\0\0\0\0\0";
+        let length = (2 + 6 + tiff.len()) as u16;
+        let mut bytes = jpeg[..2].to_vec();
+        bytes.extend_from_slice(&[0xFF, 0xE1]);
+        bytes.extend_from_slice(&length.to_be_bytes());
+        bytes.extend_from_slice(b"Exif\0\0");
+        bytes.extend_from_slice(tiff);
+        bytes.extend_from_slice(&jpeg[2..]);
+        bytes
+    }
+
+    #[test]
+    fn images_needing_no_change_keep_their_bytes() {
+        let bytes = png(&solid(100, 100));
+        let processed = pipeline()
+            .resize()
+            .strip_metadata()
+            .process("a.png", &bytes)
+            .unwrap();
+        assert!(processed.changes.is_empty());
+        assert_eq!(processed.bytes.as_ref(), bytes.as_slice());
+        assert_eq!(processed.file_name, "a.png");
+        assert_eq!((processed.width, processed.height), (100, 100));
+    }
+
+    #[test]
+    fn large_images_are_resized_when_enabled() {
+        let bytes = png(&solid(400, 100));
+
+        let err = pipeline().process("a.png", &bytes).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Image is 400x100, sides of at most 200 are accepted"
+        );
+
+        let processed = pipeline().resize().process("a.png", &bytes).unwrap();
+        assert_eq!(
+            processed.changes,
+            vec![ImageChange::Resized {
+                from: (400, 100),
+                to: (200, 50),
+            }]
+        );
+        assert_eq!((processed.width, processed.height), (200, 50));
+        let decoded = image::load_from_memory(&processed.bytes).unwrap();
+        assert_eq!(decoded.dimensions(), (200, 50));
+    }
+
+    #[test]
+    fn images_are_padded_on_white() {
+        let bytes = png(&solid(100, 50));
+        let processed = pipeline().pad_to_square().process("a.png", &bytes).unwrap();
+
+        let decoded = image::load_from_memory(&processed.bytes)
+            .unwrap()
+            .to_rgba8();
+        assert_eq!(decoded.dimensions(), (100, 100));
+        assert_eq!(*decoded.get_pixel(50, 5), Rgba([255, 255, 255, 255]));
+        assert_eq!(*decoded.get_pixel(50, 50), Rgba([20, 40, 200, 255]));
+        assert_eq!(*decoded.get_pixel(50, 95), Rgba([255, 255, 255, 255]));
+    }
+
+    #[test]
+    fn exif_is_stripped_when_enabled() {
+        let bytes = with_exif(&jpeg(&solid(100, 100), 90));
+        assert!(has_exif(&bytes));
+
+        let kept = pipeline().process("a.jpg", &bytes).unwrap();
+        assert!(kept.changes.is_empty());
+        assert!(has_exif(&kept.bytes));
+
+        let stripped = pipeline()
+            .strip_metadata()
+            .process("a.jpg", &bytes)
+            .unwrap();
+        assert_eq!(stripped.changes, vec![ImageChange::MetadataStripped]);
+        assert!(!has_exif(&stripped.bytes));
+        assert_eq!(stripped.file_name, "a.jpg");
+    }
+
+    #[test]
+    fn jpeg_quality_is_lowered_to_fit_the_size_limit() {
+        let image = noise(150, 150);
+        let max_bytes = (jpeg(&image, 90).len() + jpeg(&image, 80).len()) / 2;
+        assert!(jpeg(&image, 80).len() < max_bytes);
+
+        let processed = ImagePipeline::new()
+            .limits(max_bytes, 10, 200)
+            .format(PhotobankFormat::Jpeg)
+            .process("noise.png", &png(&image))
+            .unwrap();
+        assert_eq!(
+            processed.changes,
+            vec![
+                ImageChange::ReEncoded {
+                    from: "Png".to_string(),
+                    to: PhotobankFormat::Jpeg,
+                },
+                ImageChange::Recompressed { quality: 80 },
+            ]
+        );
+        assert!(processed.bytes.len() <= max_bytes);
+        assert_eq!(processed.file_name, "noise.jpg");
+
+        let err = ImagePipeline::new()
+            .limits(100, 10, 200)
+            .format(PhotobankFormat::Jpeg)
+            .process("noise.png", &png(&image))
+            .unwrap_err();
+        assert!(
+            err.to_string().contains("at most 100 are accepted"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn transparency_becomes_white_in_jpeg() {
+        let mut image = RgbaImage::from_pixel(20, 20, Rgba([255, 0, 0, 0]));
+        image.put_pixel(0, 0, Rgba([0, 0, 255, 255]));
+        let bytes = png(&DynamicImage::ImageRgba8(image));
+
+        let processed = pipeline()
+            .format(PhotobankFormat::Jpeg)
+            .process("logo.png", &bytes)
+            .unwrap();
+        let decoded = image::load_from_memory(&processed.bytes).unwrap().to_rgb8();
+        let Rgb([r, g, b]) = *decoded.get_pixel(15, 15);
+        assert!(r > 245 && g > 245 && b > 245, "{:?}", (r, g, b));
+    }
+
+    #[test]
+    fn partial_transparency_is_blended() {
+        let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([0, 0, 0, 128])));
+        assert_eq!(
+            *flatten_on_white(&image).get_pixel(0, 0),
+            Rgb([127, 127, 127])
+        );
+
+        let opaque = DynamicImage::ImageRgba8(RgbaImage::from_pixel(1, 1, Rgba([9, 8, 7, 255])));
+        assert_eq!(*flatten_on_white(&opaque).get_pixel(0, 0), Rgb([9, 8, 7]));
+    }
+
+    #[test]
+    fn validate_reports_every_violation() {
+        let violations = ImagePipeline::new()
+            .limits(10, 50, 200)
+            .validate(&png(&solid(300, 20)))
+            .unwrap();
+        assert_eq!(violations.len(), 3);
+        assert!(matches!(
+            violations[0],
+            ImageViolation::TooLarge { max: 10, .. }
+        ));
+        assert!(matches!(
+            violations[1],
+            ImageViolation::TooBig { width: 300, .. }
+        ));
+        assert!(matches!(
+            violations[2],
+            ImageViolation::TooSmall { height: 20, .. }
+        ));
+    }
+}
@@ -3,21 +3,75 @@ use deadpool_redis::{redis::cmd, Runtime};
 use log::info;
 use reqwest::Client;
 
+mod attribute_validation;
+mod category_diff;
+mod category_export;
+mod category_level_attribute;
+mod category_mapping;
+mod category_search;
+mod category_tree;
 mod constants;
 mod core;
+#[cfg(feature = "image")]
+mod image_pipeline;
 mod model;
 mod photobank;
+mod photobank_cleanup;
+mod photobank_sync;
+mod photobank_usage;
+mod product;
 mod product_category;
 mod product_country;
+mod product_draft;
 mod product_group;
+mod product_group_sync;
+mod signed_params;
+mod singleflight;
+mod sku;
 mod token;
 
+pub use attribute_validation::{AttributeViolation, ProductAttributeValue, ProductAttributes};
+pub use category_diff::{
+    BrokenMapping, CategoryDiff, CategoryEntry, CategoryLeafChanged, CategoryMoved,
+    CategoryRenamed, MappingProblem,
+};
+pub use category_export::NestedCategory;
+pub use category_level_attribute::{AttributeCascade, AttributeValueNode, LevelAttributeValue};
+pub use category_search::{CategoryMatch, MatchedName};
+pub use category_tree::{CategoryCrawlError, CategoryNode, CategoryTree};
+#[cfg(feature = "image")]
+pub use image_pipeline::{
+    ImageChange, ImagePipeline, ImageViolation, PhotobankFormat, ProcessedImage,
+};
+pub use model::{JavaList, NumberList, StringList};
+pub use photobank::{PhotobankImagePage, PhotobankImageQuery};
+pub use photobank_cleanup::{UnusedImageGroup, UnusedImageReport};
+pub use photobank_sync::{PhotobankManifest, PhotobankManifestEntry, PhotobankSyncReport};
+pub use photobank_usage::{PhotobankCapacity, PhotobankGroupUsage, PhotobankUsage};
+pub use product::{
+    LadderPrice, Product, ProductAttribute, ProductImages, ProductPackaging, ProductPage,
+    ProductQuery, ProductSku, ProductSkuAttribute, ProductStatus, ProductSummary, ProductTradeInfo,
+};
+pub use product_category::{
+    AttributeValue, AttributeValues, CategoryAttribute, CategoryAttributeGroup, CategoryAttributes,
+    InputType, NewCategory, ShowType, ValueType,
+};
+pub use product_draft::{DraftViolation, ProductDraft, ProductDraftError, SkuDetail};
+pub use product_group_sync::{
+    DesiredProductGroup, DesiredProductGroups, GroupParent, ProductGroupApplyError,
+    ProductGroupOperation, ProductGroupPlan,
+};
+pub use signed_params::{SignedParams, SigningKey};
+pub use sku::{Sku, SkuAttributeValue, SkuBuilder, SkuValue, SkuViolation};
+
 #[derive(Clone)]
 pub struct IopClient {
     appid: String,
-    app_secret: String,
+    signing_key: SigningKey,
     pool: deadpool_redis::Pool,
     client: Client,
+    inflight: singleflight::Group,
+    token_cache: token::TokenCache,
 }
 
 impl IopClient {
@@ -44,7 +98,7 @@ impl IopClient {
         app_secret: String,
         redis_addr: String,
     ) -> Result<Self, Box<dyn std::error::Error>> {
-        let cfg = deadpool_redis::Config::from_url(redis_addr);
+        let cfg = deadpool_redis::Config::from_url(redis_addr.clone());
         let pool = match cfg.create_pool(Some(Runtime::Tokio1)) {
             Ok(pool) => pool,
             Err(err) => {
@@ -74,11 +128,17 @@ impl IopClient {
             }
         };
 
-        Ok(IopClient {
+        let (token_cache, shutdown) = token::TokenCache::new();
+        let client = IopClient {
+            signing_key: SigningKey::new(&app_secret),
             appid,
-            app_secret,
             pool,
             client: Client::new(),
-        })
+            inflight: singleflight::Group::default(),
+            token_cache,
+        };
+        client.listen_token_invalidations(redis_addr, shutdown);
+
+        Ok(client)
     }
 }
@@ -1,9 +1,29 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug)]
+/// A list the API wraps as `{"number": [...]}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NumberList<T> {
+    pub number: Vec<T>,
+}
+
+/// A list the API wraps as `{"string": [...]}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StringList {
+    pub string: Vec<String>,
+}
+
+/// A list the API wraps as `{"java.util._list": [...]}`.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct JavaList<T> {
+    #[serde(rename = "java.util._list")]
+    pub java_util_list: Vec<T>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ProductGroup {
     pub group_id: i32,
     pub group_name: String,
+    pub parent_id: Option<i32>,
     pub children: Option<Vec<ProductGroup>>,
 }
 
@@ -19,9 +39,42 @@ pub struct PhotobankGroupList {
     pub groups: Vec<PhotoAlbumGroup>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct PhotoAlbumGroup {
     pub name: String,
     pub id: i32,
+
+    /// The top-level group of the path, equal to `id` for top-level groups.
     pub level1: i32,
 }
+
+/// An image hosted in the photo bank after an upload.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UploadedPhoto {
+    pub id: i64,
+    pub url: String,
+}
+
+/// An image stored in the photo bank.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotobankImage {
+    pub id: i64,
+    pub url: String,
+    pub display_name: Option<String>,
+
+    /// The file size in bytes.
+    #[serde(default)]
+    pub file_size: i64,
+    #[serde(default)]
+    pub width: i32,
+    #[serde(default)]
+    pub height: i32,
+
+    /// The `PhotoAlbumGroup.id` of the image, `None` if ungrouped.
+    pub group_id: Option<i32>,
+
+    /// How many products use the image.
+    #[serde(default)]
+    pub reference_count: i32,
+    pub gmt_modified: Option<String>,
+}
@@ -1,12 +1,100 @@
-use std::collections::HashMap;
+use std::{future::Future, path::Path};
 
+use bytes::Bytes;
+use chrono::NaiveDate;
+use futures::{stream, Stream, TryStreamExt};
 use log::info;
+use reqwest::multipart::{Form, Part};
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    constants::{methods, urls},
-    model, IopClient,
+    constants::{limits, methods, urls},
+    model,
+    product_group::{empty_object_as_none, validate_group_name},
+    IopClient,
 };
 
+/// Filters for listing photo bank images. Unset fields do not filter.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PhotobankImageQuery {
+    /// Only images in this `PhotoAlbumGroup.id`.
+    pub group_id: Option<i32>,
+    /// Only images whose name contains this text.
+    pub name: Option<String>,
+    /// Only images modified on or after this day.
+    pub modified_from: Option<NaiveDate>,
+    /// Only images modified on or before this day.
+    pub modified_to: Option<NaiveDate>,
+}
+
+/// One page of photo bank images.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotobankImagePage {
+    pub total_count: i32,
+    pub images: Vec<model::PhotobankImage>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankGroupAddResponse {
+    alibaba_icbu_photobank_group_add_response: PhotobankGroupAddResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankGroupAddResult {
+    group_id: i32,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankGroupUpdateResponse {
+    alibaba_icbu_photobank_group_update_response: PhotobankGroupWriteResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankGroupDeleteResponse {
+    alibaba_icbu_photobank_group_delete_response: PhotobankGroupWriteResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankGroupWriteResult {
+    result: bool,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankListResponse {
+    alibaba_icbu_photobank_list_response: PhotobankListResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankListResult {
+    #[serde(default)]
+    total_count: i32,
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    images: Option<PhotobankImageList>,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankImageList {
+    photobank_image: Vec<model::PhotobankImage>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankUploadResponse {
+    alibaba_icbu_photobank_upload_response: PhotobankUploadResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankUploadResult {
+    photobank_image: model::UploadedPhoto,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
 impl IopClient {
     /// 图片银行分组信息获取
     ///
@@ -27,23 +115,661 @@ impl IopClient {
         &self,
         id: Option<i32>,
     ) -> Result<Vec<model::PhotoAlbumGroup>, Box<dyn std::error::Error>> {
-        let mut map = HashMap::new();
+        let mut params = self.build_signed_params().await;
         if let Some(value) = id {
-            map.insert("group_id".to_string(), format!("{}", value));
+            params.insert("group_id", value.to_string());
         }
-        map.insert(
-            "method".to_string(),
-            methods::ALIBABA_ICBU_PHOTOBANK_GROUP_LIST.to_string(),
-        );
-        let params = self.build_request_params(map).await;
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_GROUP_LIST);
 
-        let hash = self.generate_sign(None, params.clone());
-        let url = self.generate_url(urls::BASE_SYNC_URL.to_string(), params.clone(), hash);
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
         info!("--------list_photo_bank_groups-------- url: {:#?}", url);
 
-        let response = self.client.get(&url).send().await?;
-        let result = response.json::<model::PhotobankGroupListResponse>().await?;
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<model::PhotobankGroupListResponse>(&body)?;
 
         Ok(result.alibaba_icbu_photobank_group_list_response.groups)
     }
+
+    /// 上传图片到图片银行
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.upload&methodType=POST)
+    ///
+    /// Uploads an image to the photo bank.
+    ///
+    /// The image is sent as the `image_bytes` part of a multipart body. The gateway
+    /// leaves file parts out of the signature, so only the other parameters are signed.
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - The `PhotoAlbumGroup.id` to upload into. If `None`, the image goes to the default group.
+    /// * `file_name` - The file name shown in the photo bank, including the extension.
+    /// * `image` - The encoded image.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the hosted `UploadedPhoto` if successful, or an error if the process fails.
+    pub async fn upload_photo_bank_image(
+        &self,
+        group_id: Option<i32>,
+        file_name: &str,
+        image: Bytes,
+    ) -> Result<model::UploadedPhoto, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("file_name", file_name);
+        if let Some(value) = group_id {
+            params.insert("group_id", value.to_string());
+        }
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_UPLOAD);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------upload_photo_bank_image-------- url: {:#?}", url);
+
+        let form = upload_form(file_name, image)?;
+        let response = self.client.post(&url).multipart(form).send().await?;
+        let result = response.json::<PhotobankUploadResponse>().await?;
+
+        Ok(result
+            .alibaba_icbu_photobank_upload_response
+            .photobank_image)
+    }
+
+    /// Uploads an image file to the photo bank under its own file name.
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - The `PhotoAlbumGroup.id` to upload into. If `None`, the image goes to the default group.
+    /// * `path` - The path of the image file.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the hosted `UploadedPhoto` if successful, or an error if the
+    /// file cannot be read or the upload fails.
+    pub async fn upload_photo_bank_file(
+        &self,
+        group_id: Option<i32>,
+        path: &Path,
+    ) -> Result<model::UploadedPhoto, Box<dyn std::error::Error>> {
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name.to_string(),
+            None => return Err(format!("Invalid image path: {}", path.display()).into()),
+        };
+        let image = tokio::fs::read(path).await?;
+
+        self.upload_photo_bank_image(group_id, &file_name, Bytes::from(image))
+            .await
+    }
+
+    /// 图片银行图片列表
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.list&methodType=GET/POST)
+    ///
+    /// Lists one page of photo bank images matching `query`.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The filters to apply.
+    /// * `current_page` - The page to retrieve, starting at `1`.
+    /// * `page_size` - The number of images per page, at most `PHOTOBANK_LIST_PAGE_SIZE`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `PhotobankImagePage` if successful, or an error if the process fails.
+    pub async fn list_photo_bank_images(
+        &self,
+        query: &PhotobankImageQuery,
+        current_page: u32,
+        page_size: u32,
+    ) -> Result<PhotobankImagePage, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("current_page", current_page.to_string());
+        params.insert("page_size", page_size.to_string());
+        if let Some(value) = query.group_id {
+            params.insert("group_id", value.to_string());
+        }
+        if let Some(value) = &query.name {
+            params.insert("name", value.as_str());
+        }
+        if let Some(value) = query.modified_from {
+            params.insert("gmt_modified_from", value.format("%Y-%m-%d").to_string());
+        }
+        if let Some(value) = query.modified_to {
+            params.insert("gmt_modified_to", value.format("%Y-%m-%d").to_string());
+        }
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_LIST);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------list_photo_bank_images-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<PhotobankListResponse>(&body)?;
+        let result = result.alibaba_icbu_photobank_list_response;
+
+        Ok(PhotobankImagePage {
+            total_count: result.total_count,
+            images: result
+                .images
+                .map(|list| list.photobank_image)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Streams every photo bank image matching `query`, walking all pages.
+    ///
+    /// Pages are fetched lazily with `list_photo_bank_images` as the stream is polled.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The filters to apply.
+    ///
+    /// # Returns
+    ///
+    /// A `Stream` of images, yielding an error and ending if a page request fails.
+    pub fn photo_bank_images(
+        &self,
+        query: PhotobankImageQuery,
+    ) -> impl Stream<Item = Result<model::PhotobankImage, Box<dyn std::error::Error>>> + '_ {
+        let page_size = limits::PHOTOBANK_LIST_PAGE_SIZE;
+
+        paginate(page_size, move |current_page| {
+            let query = query.clone();
+            async move {
+                self.list_photo_bank_images(&query, current_page, page_size)
+                    .await
+            }
+        })
+    }
+
+    /// 新增图片银行分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.group.add&methodType=GET/POST)
+    ///
+    /// Creates a photo bank group.
+    ///
+    /// The photo bank only has two group levels, so `parent_id` must be a top-level group.
+    /// The name is checked locally before the request is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - The `PhotoAlbumGroup.id` of a top-level group. If `None`, a top-level group is created.
+    /// * `name` - The name of the new group.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `PhotoAlbumGroup` if successful, or an error if the
+    /// name or parent is invalid or the request fails.
+    pub async fn add_photo_bank_group(
+        &self,
+        parent_id: Option<i32>,
+        name: String,
+    ) -> Result<model::PhotoAlbumGroup, Box<dyn std::error::Error>> {
+        let name = validate_group_name(
+            "Photo bank group",
+            &name,
+            limits::PHOTOBANK_GROUP_NAME_MAX_CHARS,
+        )?
+        .to_string();
+        if let Some(parent_id) = parent_id {
+            check_parent_group(&self.find_photo_bank_group(parent_id).await?)?;
+        }
+
+        let mut params = self.build_signed_params().await;
+        params.insert("group_name", name.as_str());
+        if let Some(value) = parent_id {
+            params.insert("parent_id", value.to_string());
+        }
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_GROUP_ADD);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------add_photo_bank_group-------- url: {:#?}", url);
+
+        let response = self.client.post(&url).send().await?;
+        let result = response.json::<PhotobankGroupAddResponse>().await?;
+        let id = result.alibaba_icbu_photobank_group_add_response.group_id;
+
+        Ok(added_group(name, id, parent_id))
+    }
+
+    /// 修改图片银行分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.group.update&methodType=GET/POST)
+    ///
+    /// Renames a photo bank group.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The `PhotoAlbumGroup.id` of the group.
+    /// * `name` - The new name.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the renamed `PhotoAlbumGroup` if successful, or an error if the
+    /// name is invalid, the group does not exist or the request fails.
+    pub async fn rename_photo_bank_group(
+        &self,
+        id: i32,
+        name: String,
+    ) -> Result<model::PhotoAlbumGroup, Box<dyn std::error::Error>> {
+        let name = validate_group_name(
+            "Photo bank group",
+            &name,
+            limits::PHOTOBANK_GROUP_NAME_MAX_CHARS,
+        )?
+        .to_string();
+        let group = self.find_photo_bank_group(id).await?;
+
+        let mut params = self.build_signed_params().await;
+        params.insert("group_id", id.to_string());
+        params.insert("group_name", name.as_str());
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_GROUP_UPDATE);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------rename_photo_bank_group-------- url: {:#?}", url);
+
+        let response = self.client.post(&url).send().await?;
+        let result = response.json::<PhotobankGroupUpdateResponse>().await?;
+        if !result.alibaba_icbu_photobank_group_update_response.result {
+            return Err(format!("Failed to rename photo bank group {id}").into());
+        }
+
+        Ok(model::PhotoAlbumGroup { name, ..group })
+    }
+
+    /// 删除图片银行分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.group.delete&methodType=GET/POST)
+    ///
+    /// Deletes an empty photo bank group.
+    ///
+    /// The group is only deleted if it has neither subgroups nor images, so no image
+    /// is silently moved or lost.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The `PhotoAlbumGroup.id` of the group.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Ok` if the group was deleted, or an error if it is not empty
+    /// or the request fails.
+    pub async fn delete_photo_bank_group(&self, id: i32) -> Result<(), Box<dyn std::error::Error>> {
+        let groups = self.list_photo_bank_groups(None).await?;
+        check_no_subgroups(id, &groups)?;
+
+        let query = PhotobankImageQuery {
+            group_id: Some(id),
+            ..Default::default()
+        };
+        check_no_images(id, &self.list_photo_bank_images(&query, 1, 1).await?)?;
+
+        let mut params = self.build_signed_params().await;
+        params.insert("group_id", id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_GROUP_DELETE);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------delete_photo_bank_group-------- url: {:#?}", url);
+
+        let response = self.client.post(&url).send().await?;
+        let result = response.json::<PhotobankGroupDeleteResponse>().await?;
+        if !result.alibaba_icbu_photobank_group_delete_response.result {
+            return Err(format!("Failed to delete photo bank group {id}").into());
+        }
+
+        Ok(())
+    }
+
+    async fn find_photo_bank_group(
+        &self,
+        id: i32,
+    ) -> Result<model::PhotoAlbumGroup, Box<dyn std::error::Error>> {
+        let groups = self.list_photo_bank_groups(Some(id)).await?;
+        find_group(groups, id)
+    }
+}
+
+/// Picks the group `id` out of a group listing.
+fn find_group(
+    groups: Vec<model::PhotoAlbumGroup>,
+    id: i32,
+) -> Result<model::PhotoAlbumGroup, Box<dyn std::error::Error>> {
+    match groups.into_iter().find(|group| group.id == id) {
+        Some(group) => Ok(group),
+        None => Err(format!("Photo bank group {id} not found").into()),
+    }
+}
+
+/// Refuses a parent that is itself a second-level group.
+fn check_parent_group(parent: &model::PhotoAlbumGroup) -> Result<(), Box<dyn std::error::Error>> {
+    if parent.level1 != parent.id {
+        return Err(format!(
+            "Photo bank group {} is a second-level group and cannot have children",
+            parent.id
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Refuses to delete a group that still has subgroups.
+fn check_no_subgroups(
+    id: i32,
+    groups: &[model::PhotoAlbumGroup],
+) -> Result<(), Box<dyn std::error::Error>> {
+    if groups
+        .iter()
+        .any(|group| group.level1 == id && group.id != id)
+    {
+        return Err(format!("Photo bank group {id} still has subgroups").into());
+    }
+    Ok(())
+}
+
+/// Refuses to delete a group that still has images, given its first image page.
+fn check_no_images(id: i32, page: &PhotobankImagePage) -> Result<(), Box<dyn std::error::Error>> {
+    if page.total_count > 0 || !page.images.is_empty() {
+        return Err(format!("Photo bank group {id} still has images").into());
+    }
+    Ok(())
+}
+
+/// The group created by `add_photo_bank_group`.
+fn added_group(name: String, id: i32, parent_id: Option<i32>) -> model::PhotoAlbumGroup {
+    model::PhotoAlbumGroup {
+        name,
+        id,
+        level1: parent_id.unwrap_or(id),
+    }
+}
+
+/// Builds the multipart body of an upload, the image being the `image_bytes` part.
+fn upload_form(file_name: &str, image: Bytes) -> Result<Form, Box<dyn std::error::Error>> {
+    let part = Part::stream(image)
+        .file_name(file_name.to_string())
+        .mime_str(content_type(file_name))?;
+    Ok(Form::new().part("image_bytes", part))
+}
+
+/// Streams the images of every page fetched through `fetch`, starting at page `1`.
+fn paginate<'a, F, Fut>(
+    page_size: u32,
+    fetch: F,
+) -> impl Stream<Item = Result<model::PhotobankImage, Box<dyn std::error::Error>>> + 'a
+where
+    F: Fn(u32) -> Fut + 'a,
+    Fut: Future<Output = Result<PhotobankImagePage, Box<dyn std::error::Error>>> + 'a,
+{
+    stream::try_unfold(Some(1), move |current_page: Option<u32>| {
+        let request = current_page.map(|current_page| (current_page, fetch(current_page)));
+        async move {
+            let (current_page, request) = match request {
+                Some(request) => request,
+                None => return Ok::<_, Box<dyn std::error::Error>>(None),
+            };
+
+            let page = request.await?;
+            let next = next_page(current_page, page_size, &page);
+            Ok(Some((page.images, next)))
+        }
+    })
+    .map_ok(|images| stream::iter(images.into_iter().map(Ok)))
+    .try_flatten()
+}
+
+/// Returns the page after `current_page`, or `None` if `page` was the last one.
+fn next_page(current_page: u32, page_size: u32, page: &PhotobankImagePage) -> Option<u32> {
+    let fetched = current_page as i64 * page_size as i64;
+    if page.images.len() < page_size as usize || fetched >= page.total_count as i64 {
+        None
+    } else {
+        Some(current_page + 1)
+    }
+}
+
+pub(crate) fn content_type(file_name: &str) -> &'static str {
+    let extension = Path::new(file_name)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("jpg") | Some("jpeg") => "image/jpeg",
+        Some("png") => "image/png",
+        Some("gif") => "image/gif",
+        Some("bmp") => "image/bmp",
+        Some("webp") => "image/webp",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::StreamExt;
+    use std::{
+        io::{Read, Write},
+        sync::Mutex,
+    };
+
+    fn image(id: i64) -> model::PhotobankImage {
+        model::PhotobankImage {
+            id,
+            url: format!("https://sc04.alicdn.com/kf/H{id}.jpg"),
+            display_name: None,
+            file_size: 0,
+            width: 0,
+            height: 0,
+            group_id: None,
+            reference_count: 0,
+            gmt_modified: None,
+        }
+    }
+
+    fn page(total_count: i32, ids: std::ops::Range<i64>) -> PhotobankImagePage {
+        PhotobankImagePage {
+            total_count,
+            images: ids.map(image).collect(),
+        }
+    }
+
+    /// Accepts one HTTP request and returns its raw bytes, answering with `{}`.
+    fn capture_request() -> (String, std::sync::mpsc::Receiver<Vec<u8>>) {
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/upload", server.local_addr().unwrap());
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        std::thread::spawn(move || {
+            let (mut stream, _) = server.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            loop {
+                let read = stream.read(&mut buffer).unwrap();
+                request.extend_from_slice(&buffer[..read]);
+
+                let text = String::from_utf8_lossy(&request);
+                if let Some(end) = text.find("\r\n\r\n") {
+                    let length = text[..end]
+                        .lines()
+                        .find_map(|line| {
+                            line.to_lowercase()
+                                .strip_prefix("content-length: ")
+                                .map(str::to_string)
+                        })
+                        .and_then(|length| length.trim().parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if request.len() >= end + 4 + length || read == 0 {
+                        break;
+                    }
+                }
+            }
+
+            stream
+                .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\n{}")
+                .unwrap();
+            sender.send(request).unwrap();
+        });
+
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn upload_sends_the_image_as_a_file_part() {
+        let (url, request) = capture_request();
+        let form = upload_form("Red Shoe.PNG", Bytes::from_static(b"\x89PNG-data")).unwrap();
+        reqwest::Client::new()
+            .post(&url)
+            .multipart(form)
+            .send()
+            .await
+            .unwrap();
+
+        let request = request.recv().unwrap();
+        let text = String::from_utf8_lossy(&request);
+        let boundary = text
+            .lines()
+            .find_map(|line| {
+                line.to_lowercase()
+                    .strip_prefix("content-type: multipart/form-data; boundary=")
+                    .map(str::to_string)
+            })
+            .expect("multipart content type");
+
+        assert!(text.contains(
+            "Content-Disposition: form-data; name=\"image_bytes\"; filename=\"Red Shoe.PNG\"\r\n"
+        ));
+        assert!(text.contains("Content-Type: image/png\r\n\r\n\u{fffd}PNG-data\r\n"));
+        assert!(text.ends_with(&format!("--{boundary}--\r\n")));
+        assert_eq!(text.matches("Content-Disposition").count(), 1);
+    }
+
+    #[test]
+    fn content_type_follows_the_extension() {
+        assert_eq!(content_type("a.JPG"), "image/jpeg");
+        assert_eq!(content_type("a.jpeg"), "image/jpeg");
+        assert_eq!(content_type("a.webp"), "image/webp");
+        assert_eq!(content_type("a.tiff"), "application/octet-stream");
+        assert_eq!(content_type("jpg"), "application/octet-stream");
+    }
+
+    #[test]
+    fn next_page_stops_on_short_or_last_pages() {
+        assert_eq!(next_page(1, 2, &page(5, 0..2)), Some(2));
+        assert_eq!(next_page(2, 2, &page(5, 2..4)), Some(3));
+        assert_eq!(next_page(3, 2, &page(5, 4..5)), None);
+        assert_eq!(next_page(2, 2, &page(4, 2..4)), None);
+        // A full page is the last one once the total is reached, even if the total
+        // reported by the gateway is stale.
+        assert_eq!(next_page(1, 2, &page(1, 0..2)), None);
+        assert_eq!(next_page(1, 2, &page(0, 0..0)), None);
+    }
+
+    #[tokio::test]
+    async fn paginate_walks_every_page_in_order() {
+        let requested = Mutex::new(Vec::new());
+        let images: Vec<_> = paginate(2, |current_page| {
+            requested.lock().unwrap().push(current_page);
+            let start = (current_page as i64 - 1) * 2;
+            let page = page(5, start..(start + 2).min(5));
+            async move { Ok(page) }
+        })
+        .try_collect()
+        .await
+        .unwrap();
+
+        assert_eq!(
+            images.iter().map(|image| image.id).collect::<Vec<_>>(),
+            vec![0, 1, 2, 3, 4]
+        );
+        assert_eq!(requested.into_inner().unwrap(), vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn paginate_yields_the_error_and_ends() {
+        let results: Vec<_> = paginate(2, |current_page| async move {
+            if current_page == 2 {
+                Err("gateway timeout".into())
+            } else {
+                Ok(page(6, 0..2))
+            }
+        })
+        .collect()
+        .await;
+
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok() && results[1].is_ok());
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "gateway timeout"
+        );
+    }
+
+    fn group(id: i32, level1: i32) -> model::PhotoAlbumGroup {
+        model::PhotoAlbumGroup {
+            name: format!("Group {id}"),
+            id,
+            level1,
+        }
+    }
+
+    #[test]
+    fn groups_are_added_below_top_level_groups_only() {
+        assert!(check_parent_group(&group(10, 10)).is_ok());
+        assert_eq!(
+            check_parent_group(&group(11, 10)).unwrap_err().to_string(),
+            "Photo bank group 11 is a second-level group and cannot have children"
+        );
+
+        let top = added_group("Shoes".to_string(), 20, None);
+        assert_eq!((top.id, top.level1), (20, 20));
+        let nested = added_group("Boots".to_string(), 21, Some(20));
+        assert_eq!(
+            (nested.id, nested.level1, nested.name.as_str()),
+            (21, 20, "Boots")
+        );
+    }
+
+    #[test]
+    fn groups_are_found_by_id() {
+        let groups = vec![group(10, 10), group(11, 10)];
+        assert_eq!(find_group(groups.clone(), 11).unwrap().id, 11);
+        assert_eq!(
+            find_group(groups, 12).unwrap_err().to_string(),
+            "Photo bank group 12 not found"
+        );
+    }
+
+    #[test]
+    fn only_empty_groups_are_deleted() {
+        let groups = vec![group(10, 10), group(11, 10), group(20, 20)];
+        assert!(check_no_subgroups(10, &groups).is_err());
+        assert!(check_no_subgroups(11, &groups).is_ok());
+        assert!(check_no_subgroups(20, &groups).is_ok());
+
+        assert!(check_no_images(20, &page(0, 0..0)).is_ok());
+        assert_eq!(
+            check_no_images(20, &page(3, 0..1)).unwrap_err().to_string(),
+            "Photo bank group 20 still has images"
+        );
+        // The total is not trusted alone.
+        assert!(check_no_images(20, &page(0, 0..1)).is_err());
+    }
+
+    #[test]
+    fn group_write_responses_deserialize() {
+        let added: PhotobankGroupAddResponse = serde_json::from_str(
+            r#"{"alibaba_icbu_photobank_group_add_response":{"group_id":31,"request_id":"x"}}"#,
+        )
+        .unwrap();
+        assert_eq!(added.alibaba_icbu_photobank_group_add_response.group_id, 31);
+
+        let updated: PhotobankGroupUpdateResponse = serde_json::from_str(
+            r#"{"alibaba_icbu_photobank_group_update_response":{"result":true}}"#,
+        )
+        .unwrap();
+        assert!(updated.alibaba_icbu_photobank_group_update_response.result);
+
+        let deleted: PhotobankGroupDeleteResponse = serde_json::from_str(
+            r#"{"alibaba_icbu_photobank_group_delete_response":{"result":false}}"#,
+        )
+        .unwrap();
+        assert!(!deleted.alibaba_icbu_photobank_group_delete_response.result);
+    }
 }
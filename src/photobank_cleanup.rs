@@ -0,0 +1,430 @@
+use std::collections::{BTreeMap, HashSet};
+
+use futures::TryStreamExt;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{limits, methods, urls},
+    model,
+    photobank::PhotobankImageQuery,
+    product::ProductQuery,
+    IopClient,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankDeleteResponse {
+    alibaba_icbu_photobank_delete_response: PhotobankDeleteResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankDeleteResult {
+    result: bool,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+/// The unreferenced images of one photo bank group.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct UnusedImageGroup {
+    /// The `PhotoAlbumGroup.id`, `None` for ungrouped images.
+    pub group_id: Option<i32>,
+    pub group_name: Option<String>,
+    pub images: Vec<model::PhotobankImage>,
+
+    /// The total size of `images` in bytes.
+    pub bytes: i64,
+}
+
+/// The photo bank images no product uses, by group.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct UnusedImageReport {
+    pub groups: Vec<UnusedImageGroup>,
+    /// The number of images scanned.
+    pub scanned: usize,
+}
+
+impl UnusedImageReport {
+    /// Returns the IDs of all unreferenced images.
+    pub fn image_ids(&self) -> Vec<i64> {
+        self.groups
+            .iter()
+            .flat_map(|group| group.images.iter().map(|image| image.id))
+            .collect()
+    }
+
+    /// Returns the total size of all unreferenced images in bytes.
+    pub fn bytes(&self) -> i64 {
+        self.groups.iter().map(|group| group.bytes).sum()
+    }
+}
+
+impl IopClient {
+    /// Collects every image URL used by the seller's products.
+    ///
+    /// Products are listed with `products`, then each is fetched with `get_product`
+    /// for `Product::image_urls`, keeping at most `concurrency` requests in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `concurrency` - The maximum number of concurrent `get_product` requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the image URLs if successful, or an error if a request fails.
+    pub async fn product_image_urls(
+        &self,
+        concurrency: usize,
+    ) -> Result<HashSet<String>, Box<dyn std::error::Error>> {
+        let urls = self
+            .products(ProductQuery::default())
+            .map_ok(|product| self.get_product(product.id))
+            .try_buffer_unordered(concurrency.max(1))
+            .try_fold(HashSet::new(), |mut urls, product| async move {
+                urls.extend(product.image_urls());
+                Ok(urls)
+            })
+            .await?;
+
+        Ok(urls)
+    }
+
+    /// Finds the photo bank images not used by any product.
+    ///
+    /// The image URLs of every product are collected with `product_image_urls`, then
+    /// every image is listed with `photo_bank_images` and compared against them,
+    /// ignoring the scheme, query string and thumbnail suffixes such as
+    /// `_350x350.jpg`. Images the photo bank itself reports as referenced are never
+    /// considered unused.
+    ///
+    /// # Arguments
+    ///
+    /// * `concurrency` - The maximum number of concurrent `get_product` requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `UnusedImageReport` if successful, or an error if a
+    /// request fails.
+    pub async fn find_unused_photo_bank_images(
+        &self,
+        concurrency: usize,
+    ) -> Result<UnusedImageReport, Box<dyn std::error::Error>> {
+        let referenced: HashSet<String> = self
+            .product_image_urls(concurrency)
+            .await?
+            .iter()
+            .map(|url| normalize_image_url(url))
+            .collect();
+
+        let groups = self.list_photo_bank_groups(None).await?;
+        let images: Vec<model::PhotobankImage> = self
+            .photo_bank_images(PhotobankImageQuery::default())
+            .try_collect()
+            .await?;
+
+        Ok(unused_images(images, &referenced, &groups))
+    }
+
+    /// 删除图片银行图片
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.delete&methodType=GET/POST)
+    ///
+    /// Deletes photo bank images, in batches of `PHOTOBANK_DELETE_BATCH_SIZE`.
+    ///
+    /// # Arguments
+    ///
+    /// * `image_ids` - The `PhotobankImage.id` of each image to delete.
+    /// * `dry_run` - If `true`, only logs what would be deleted.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the IDs deleted, or that would be deleted in a dry run, if
+    /// successful, or an error if a batch fails. Batches before the failing one stay deleted.
+    pub async fn delete_photo_bank_images(
+        &self,
+        image_ids: &[i64],
+        dry_run: bool,
+    ) -> Result<Vec<i64>, Box<dyn std::error::Error>> {
+        if dry_run {
+            info!(
+                "--------delete_photo_bank_images-------- dry run: {:?}",
+                image_ids
+            );
+            return Ok(image_ids.to_vec());
+        }
+
+        let mut deleted = Vec::with_capacity(image_ids.len());
+        for batch in image_ids.chunks(limits::PHOTOBANK_DELETE_BATCH_SIZE) {
+            let ids = batch
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            let mut params = self.build_signed_params().await;
+            params.insert("image_ids", ids);
+            params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_DELETE);
+
+            let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+            info!("--------delete_photo_bank_images-------- url: {:#?}", url);
+
+            let response = self.client.post(&url).send().await?;
+            let result = response.json::<PhotobankDeleteResponse>().await?;
+            if !result.alibaba_icbu_photobank_delete_response.result {
+                return Err(format!(
+                    "Failed to delete photo bank images {:?}, {} deleted before",
+                    batch,
+                    deleted.len()
+                )
+                .into());
+            }
+
+            deleted.extend_from_slice(batch);
+        }
+
+        Ok(deleted)
+    }
+}
+
+/// Groups the images whose normalized URL is not in `referenced`.
+fn unused_images(
+    images: Vec<model::PhotobankImage>,
+    referenced: &HashSet<String>,
+    groups: &[model::PhotoAlbumGroup],
+) -> UnusedImageReport {
+    let scanned = images.len();
+    let mut unused: BTreeMap<Option<i32>, Vec<model::PhotobankImage>> = BTreeMap::new();
+    for image in images {
+        if image.reference_count > 0 || referenced.contains(&normalize_image_url(&image.url)) {
+            continue;
+        }
+        unused.entry(image.group_id).or_default().push(image);
+    }
+
+    UnusedImageReport {
+        groups: unused
+            .into_iter()
+            .map(|(group_id, images)| UnusedImageGroup {
+                group_id,
+                group_name: groups
+                    .iter()
+                    .find(|group| Some(group.id) == group_id)
+                    .map(|group| group.name.clone()),
+                bytes: images.iter().map(|image| image.file_size).sum(),
+                images,
+            })
+            .collect(),
+        scanned,
+    }
+}
+
+/// Reduces an image URL to the part identifying the hosted file: no scheme, query
+/// string, fragment or thumbnail suffix, and a lowercase host.
+fn normalize_image_url(url: &str) -> String {
+    let url = url.trim();
+    let url = ["https:", "http:"]
+        .iter()
+        .find(|scheme| {
+            url.get(..scheme.len())
+                .map_or(false, |prefix| prefix.eq_ignore_ascii_case(scheme))
+        })
+        .map_or(url, |scheme| &url[scheme.len()..]);
+    let url = url.strip_prefix("//").unwrap_or(url);
+    let url = match url.find(['?', '#']) {
+        Some(index) => &url[..index],
+        None => url,
+    };
+
+    let url = strip_thumbnail_suffix(url);
+    match url.find('/') {
+        Some(index) => format!("{}{}", url[..index].to_ascii_lowercase(), &url[index..]),
+        None => url.to_ascii_lowercase(),
+    }
+}
+
+/// Strips a resize suffix such as `_350x350.jpg`, `_350x350q90.jpg` or `_.webp` from
+/// `name.jpg_350x350.jpg`. Names that only look alike, such as `banner_350x350.jpg`,
+/// are kept.
+fn strip_thumbnail_suffix(url: &str) -> &str {
+    let index = match url.rfind('_') {
+        Some(index) => index,
+        None => return url,
+    };
+
+    let (base, suffix) = (&url[..index], &url[index + 1..]);
+    let (transform, extension) = match suffix.rsplit_once('.') {
+        Some(parts) => parts,
+        None => return url,
+    };
+    let base_extension = base
+        .rsplit('/')
+        .next()
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension);
+
+    if is_image_extension(extension)
+        && base_extension.map_or(false, is_image_extension)
+        && is_resize_transform(transform)
+    {
+        base
+    } else {
+        url
+    }
+}
+
+fn is_image_extension(extension: &str) -> bool {
+    ["jpg", "jpeg", "png", "gif", "webp", "bmp"]
+        .iter()
+        .any(|known| extension.eq_ignore_ascii_case(known))
+}
+
+/// Accepts an empty transform or `<width>x<height>` followed by options such as
+/// `q90` or `xz`.
+fn is_resize_transform(transform: &str) -> bool {
+    if transform.is_empty() {
+        return true;
+    }
+
+    let (width, rest) = transform.split_at(
+        transform
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(transform.len()),
+    );
+    let rest = match rest.strip_prefix('x') {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let (height, options) = rest.split_at(
+        rest.find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len()),
+    );
+
+    !width.is_empty() && !height.is_empty() && options.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOSTED: &str = "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg";
+
+    fn image(
+        id: i64,
+        url: &str,
+        group_id: Option<i32>,
+        reference_count: i32,
+    ) -> model::PhotobankImage {
+        model::PhotobankImage {
+            id,
+            url: url.to_string(),
+            display_name: None,
+            file_size: 100,
+            width: 0,
+            height: 0,
+            group_id,
+            reference_count,
+            gmt_modified: None,
+        }
+    }
+
+    #[test]
+    fn scheme_is_ignored() {
+        let expected = normalize_image_url(HOSTED);
+        assert_eq!(expected, "sc04.alicdn.com/kf/H7a1b2C3d.jpg");
+
+        for url in [
+            "http://sc04.alicdn.com/kf/H7a1b2C3d.jpg",
+            "HTTPS://sc04.alicdn.com/kf/H7a1b2C3d.jpg",
+            "//sc04.alicdn.com/kf/H7a1b2C3d.jpg",
+            "sc04.alicdn.com/kf/H7a1b2C3d.jpg",
+            "  https://sc04.alicdn.com/kf/H7a1b2C3d.jpg\n",
+        ] {
+            assert_eq!(normalize_image_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn query_and_fragment_are_ignored() {
+        let expected = normalize_image_url(HOSTED);
+
+        for url in [
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg?x-oss-process=image/resize",
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg#zoom",
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg?a=1#b_350x350.jpg",
+        ] {
+            assert_eq!(normalize_image_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn host_case_is_ignored_but_path_case_is_kept() {
+        assert_eq!(
+            normalize_image_url("https://SC04.AliCDN.com/kf/H7a1b2C3d.jpg"),
+            normalize_image_url(HOSTED)
+        );
+        assert_ne!(
+            normalize_image_url("https://sc04.alicdn.com/kf/h7a1b2c3d.jpg"),
+            normalize_image_url(HOSTED)
+        );
+    }
+
+    #[test]
+    fn thumbnail_suffixes_are_stripped() {
+        let expected = normalize_image_url(HOSTED);
+
+        for url in [
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg_350x350.jpg",
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg_50x50.png",
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg_220x220q90.jpg",
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg_640x640xz.jpg",
+            "https://sc04.alicdn.com/kf/H7a1b2C3d.jpg_.webp",
+            "//sc04.alicdn.com/kf/H7a1b2C3d.jpg_350x350.jpg?a=1",
+        ] {
+            assert_eq!(normalize_image_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn names_resembling_a_thumbnail_are_kept() {
+        for url in [
+            "https://sc04.alicdn.com/kf/banner_350x350.jpg",
+            "https://sc04.alicdn.com/kf/v1.2_10x10.jpg",
+            "https://sc04.alicdn.com/kf/photo.jpg_large.jpg",
+            "https://sc04.alicdn.com/kf/photo.jpg_350x.jpg",
+            "https://sc04.alicdn.com/kf/photo.jpg_x350.jpg",
+            "https://sc04.alicdn.com/kf/photo.jpg_350x350",
+            "https://sc04.alicdn.com/kf/photo.jpg_350x350.txt",
+            "https://sc04.alicdn.com/kf_350x350.jpg/photo.jpg",
+        ] {
+            let expected = url.trim_start_matches("https://");
+            assert_eq!(normalize_image_url(url), expected, "{url}");
+        }
+    }
+
+    #[test]
+    fn unused_images_exclude_referenced_ones() {
+        let images = vec![
+            image(1, HOSTED, Some(10), 0),
+            image(2, "https://sc04.alicdn.com/kf/Hunused.jpg", Some(10), 0),
+            image(3, "https://sc04.alicdn.com/kf/Hcounted.jpg", Some(10), 1),
+            image(4, "https://sc04.alicdn.com/kf/Hloose.png", None, 0),
+        ];
+        let referenced: HashSet<String> = ["//sc04.alicdn.com/kf/H7a1b2C3d.jpg_350x350.jpg"]
+            .iter()
+            .map(|url| normalize_image_url(url))
+            .collect();
+        let groups = vec![model::PhotoAlbumGroup {
+            name: "Shoes".to_string(),
+            id: 10,
+            level1: 10,
+        }];
+
+        let report = unused_images(images, &referenced, &groups);
+
+        assert_eq!(report.scanned, 4);
+        assert_eq!(report.image_ids(), [4, 2]);
+        assert_eq!(report.bytes(), 200);
+        assert_eq!(report.groups[0].group_name, None);
+        assert_eq!(report.groups[1].group_name.as_deref(), Some("Shoes"));
+    }
+}
@@ -0,0 +1,488 @@
+use std::{
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
+    io::{ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use bytes::Bytes;
+use futures::TryStreamExt;
+use log::info;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    model,
+    photobank::{content_type, PhotobankImageQuery},
+    IopClient,
+};
+
+/// Where a local file is hosted in the photo bank.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct PhotobankManifestEntry {
+    /// The hex SHA-256 of the uploaded content.
+    pub sha256: String,
+    pub group_id: Option<i32>,
+    pub id: i64,
+    pub url: String,
+}
+
+/// Maps local image paths to the photo bank images they were uploaded as.
+///
+/// Paths are relative to the synchronized directory and use `/` separators, e.g.
+/// `Shoes/Boots/black.jpg`.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PhotobankManifest {
+    #[serde(default)]
+    pub files: BTreeMap<String, PhotobankManifestEntry>,
+}
+
+impl PhotobankManifest {
+    /// Returns the hosted URL of a local image, if it was synchronized.
+    pub fn url(&self, relative_path: &str) -> Option<&str> {
+        self.files
+            .get(relative_path)
+            .map(|entry| entry.url.as_str())
+    }
+
+    /// Loads a manifest written by `write_json`.
+    pub fn read_json<R: Read>(reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Writes the manifest as a JSON document.
+    pub fn write_json<W: Write>(&self, writer: W) -> Result<(), Box<dyn std::error::Error>> {
+        serde_json::to_writer_pretty(writer, self)?;
+        Ok(())
+    }
+
+    /// Returns `true` if `relative_path` was synchronized with this content.
+    fn is_unchanged(&self, relative_path: &str, sha256: &str) -> bool {
+        self.files
+            .get(relative_path)
+            .map_or(false, |entry| entry.sha256 == sha256)
+    }
+
+    /// Finds an upload of the same content into the same group.
+    fn find_content(&self, sha256: &str, group_id: Option<i32>) -> Option<&PhotobankManifestEntry> {
+        self.files
+            .values()
+            .find(|entry| entry.sha256 == sha256 && entry.group_id == group_id)
+    }
+
+    /// Returns `true` if a hosted image may be matched to `relative_path` by name
+    /// and size. A changed file keeps its name and often its size, so only files
+    /// the manifest does not know yet qualify.
+    fn may_match_by_name(&self, relative_path: &str) -> bool {
+        !self.files.contains_key(relative_path)
+    }
+
+    /// Drops the entries of files not in `relative_paths`, returning their paths.
+    fn prune(&mut self, relative_paths: &HashSet<String>) -> Vec<String> {
+        let removed: Vec<String> = self
+            .files
+            .keys()
+            .filter(|path| !relative_paths.contains(*path))
+            .cloned()
+            .collect();
+        for path in &removed {
+            self.files.remove(path);
+        }
+        removed
+    }
+
+    /// Replaces the manifest file through a temporary file in the same directory,
+    /// so an interruption never leaves a truncated manifest behind.
+    async fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        let file_name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(file_name) => file_name,
+            None => return Err(format!("Invalid manifest path: {}", path.display()).into()),
+        };
+        let temporary = path.with_file_name(format!(".{file_name}.tmp"));
+
+        let mut buffer = Vec::new();
+        self.write_json(&mut buffer)?;
+        tokio::fs::write(&temporary, buffer).await?;
+        tokio::fs::rename(&temporary, path).await?;
+        Ok(())
+    }
+}
+
+/// What `sync_photo_bank_directory` did, by relative path.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PhotobankSyncReport {
+    /// Files uploaded because they were new or changed.
+    pub uploaded: Vec<String>,
+    /// Files whose content matches the manifest.
+    pub unchanged: Vec<String>,
+    /// Files not uploaded because the same image was already in their group.
+    pub duplicates: Vec<String>,
+    /// Manifest entries dropped because their file no longer exists.
+    pub removed: Vec<String>,
+    pub created_groups: Vec<model::PhotoAlbumGroup>,
+    pub manifest: PhotobankManifest,
+}
+
+struct LocalImage {
+    path: PathBuf,
+    relative_path: String,
+    file_name: String,
+    groups: Vec<String>,
+}
+
+impl IopClient {
+    /// Synchronizes a local directory of images into the photo bank.
+    ///
+    /// Subdirectories map to photo bank groups by name, the first level to top-level
+    /// groups and the second to their subgroups; missing groups are created and files
+    /// directly in `root` go to the default group. A file is uploaded when its SHA-256
+    /// differs from the manifest, unless the manifest already holds the same content
+    /// in its group. Files new to the manifest are also matched against the images
+    /// already in their group by name and size, so a first run does not upload them
+    /// again. Entries of files that no longer exist are dropped from the manifest.
+    /// The manifest is saved after every file, so an interrupted run resumes where
+    /// it stopped.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The directory to synchronize.
+    /// * `manifest_path` - The JSON manifest to read and update, created if missing.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `PhotobankSyncReport` if successful, or an error if the
+    /// directory is nested deeper than two levels, a file cannot be read or a request fails.
+    pub async fn sync_photo_bank_directory(
+        &self,
+        root: &Path,
+        manifest_path: &Path,
+    ) -> Result<PhotobankSyncReport, Box<dyn std::error::Error>> {
+        let mut manifest = match tokio::fs::read(manifest_path).await {
+            Ok(bytes) => PhotobankManifest::read_json(&bytes[..])?,
+            Err(err) if err.kind() == ErrorKind::NotFound => PhotobankManifest::default(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let images = scan_directory(root).await?;
+        let relative_paths: HashSet<String> = images
+            .iter()
+            .map(|image| image.relative_path.clone())
+            .collect();
+        let mut groups = self.list_photo_bank_groups(None).await?;
+        let mut hosted: HashMap<i32, Vec<model::PhotobankImage>> = HashMap::new();
+        let mut report = PhotobankSyncReport::default();
+
+        for image in images {
+            let content = tokio::fs::read(&image.path).await?;
+            let sha256 = sha256_hex(&content);
+
+            if manifest.is_unchanged(&image.relative_path, &sha256) {
+                report.unchanged.push(image.relative_path);
+                continue;
+            }
+
+            let group_id = self
+                .ensure_photo_bank_group(&image.groups, &mut groups, &mut report.created_groups)
+                .await?;
+
+            let known = manifest.find_content(&sha256, group_id).cloned();
+
+            let entry = match known {
+                Some(entry) => {
+                    report.duplicates.push(image.relative_path.clone());
+                    entry
+                }
+                None => {
+                    let existing = match group_id {
+                        Some(group_id) if manifest.may_match_by_name(&image.relative_path) => {
+                            self.find_hosted_image(
+                                group_id,
+                                &mut hosted,
+                                &image.file_name,
+                                content.len(),
+                            )
+                            .await?
+                        }
+                        _ => None,
+                    };
+
+                    match existing {
+                        Some(hosted) => {
+                            report.duplicates.push(image.relative_path.clone());
+                            PhotobankManifestEntry {
+                                sha256,
+                                group_id,
+                                id: hosted.id,
+                                url: hosted.url,
+                            }
+                        }
+                        None => {
+                            info!(
+                                "--------sync_photo_bank_directory-------- upload: {}",
+                                image.relative_path
+                            );
+                            let uploaded = self
+                                .upload_photo_bank_image(
+                                    group_id,
+                                    &image.file_name,
+                                    Bytes::from(content),
+                                )
+                                .await?;
+                            report.uploaded.push(image.relative_path.clone());
+                            PhotobankManifestEntry {
+                                sha256,
+                                group_id,
+                                id: uploaded.id,
+                                url: uploaded.url,
+                            }
+                        }
+                    }
+                }
+            };
+
+            manifest.files.insert(image.relative_path, entry);
+            manifest.save(manifest_path).await?;
+        }
+
+        report.removed = manifest.prune(&relative_paths);
+        if !report.removed.is_empty() {
+            manifest.save(manifest_path).await?;
+        }
+
+        report.manifest = manifest;
+        Ok(report)
+    }
+
+    /// Finds an image of the same name and size in a group, listing each group once.
+    async fn find_hosted_image(
+        &self,
+        group_id: i32,
+        hosted: &mut HashMap<i32, Vec<model::PhotobankImage>>,
+        file_name: &str,
+        file_size: usize,
+    ) -> Result<Option<model::PhotobankImage>, Box<dyn std::error::Error>> {
+        let images = match hosted.entry(group_id) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let query = PhotobankImageQuery {
+                    group_id: Some(group_id),
+                    ..Default::default()
+                };
+                entry.insert(self.photo_bank_images(query).try_collect().await?)
+            }
+        };
+
+        Ok(find_by_name_and_size(images, file_name, file_size).cloned())
+    }
+
+    /// Returns the group for a directory path, creating missing groups.
+    async fn ensure_photo_bank_group(
+        &self,
+        path: &[String],
+        groups: &mut Vec<model::PhotoAlbumGroup>,
+        created: &mut Vec<model::PhotoAlbumGroup>,
+    ) -> Result<Option<i32>, Box<dyn std::error::Error>> {
+        let mut parent: Option<i32> = None;
+
+        for name in path {
+            let found = groups.iter().find(|group| {
+                let level = match parent {
+                    Some(parent) => group.level1 == parent && group.id != parent,
+                    None => group.level1 == group.id,
+                };
+                level && &group.name == name
+            });
+
+            let id = match found {
+                Some(group) => group.id,
+                None => {
+                    let group = self.add_photo_bank_group(parent, name.clone()).await?;
+                    groups.push(group.clone());
+                    created.push(group.clone());
+                    group.id
+                }
+            };
+            parent = Some(id);
+        }
+
+        Ok(parent)
+    }
+}
+
+fn sha256_hex(content: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(content))
+}
+
+/// Finds a hosted image with the given display name and size in bytes.
+fn find_by_name_and_size<'a>(
+    images: &'a [model::PhotobankImage],
+    file_name: &str,
+    file_size: usize,
+) -> Option<&'a model::PhotobankImage> {
+    images.iter().find(|image| {
+        image.display_name.as_deref() == Some(file_name) && image.file_size == file_size as i64
+    })
+}
+
+/// Lists the images below `root`, sorted by relative path.
+async fn scan_directory(root: &Path) -> Result<Vec<LocalImage>, Box<dyn std::error::Error>> {
+    let mut images = Vec::new();
+    let mut pending = vec![(root.to_path_buf(), Vec::<String>::new())];
+
+    while let Some((directory, groups)) = pending.pop() {
+        let mut entries = tokio::fs::read_dir(&directory).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let name = match entry.file_name().into_string() {
+                Ok(name) => name,
+                Err(name) => return Err(format!("Invalid file name: {:?}", name).into()),
+            };
+            if name.starts_with('.') {
+                continue;
+            }
+
+            let path = entry.path();
+            if entry.file_type().await?.is_dir() {
+                if groups.len() == 2 {
+                    return Err(format!(
+                        "Photo bank groups have two levels, {} is nested deeper",
+                        path.display()
+                    )
+                    .into());
+                }
+                let mut groups = groups.clone();
+                groups.push(name);
+                pending.push((path, groups));
+            } else if content_type(&name).starts_with("image/") {
+                let mut relative_path = groups.join("/");
+                if !relative_path.is_empty() {
+                    relative_path.push('/');
+                }
+                relative_path.push_str(&name);
+
+                images.push(LocalImage {
+                    path,
+                    relative_path,
+                    file_name: name,
+                    groups: groups.clone(),
+                });
+            }
+        }
+    }
+
+    images.sort_by(|a, b| a.relative_path.cmp(&b.relative_path));
+    Ok(images)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(sha256: &str, group_id: Option<i32>, id: i64) -> PhotobankManifestEntry {
+        PhotobankManifestEntry {
+            sha256: sha256.to_string(),
+            group_id,
+            id,
+            url: format!("https://sc04.alicdn.com/kf/H{id}.jpg"),
+        }
+    }
+
+    fn manifest() -> PhotobankManifest {
+        PhotobankManifest {
+            files: BTreeMap::from([
+                ("Shoes/black.jpg".to_string(), entry("aa", Some(1), 10)),
+                ("Shoes/white.jpg".to_string(), entry("bb", Some(1), 11)),
+                ("logo.png".to_string(), entry("cc", None, 12)),
+            ]),
+        }
+    }
+
+    fn hosted(id: i64, display_name: &str, file_size: i64) -> model::PhotobankImage {
+        model::PhotobankImage {
+            id,
+            url: format!("https://sc04.alicdn.com/kf/H{id}.jpg"),
+            display_name: Some(display_name.to_string()),
+            file_size,
+            width: 0,
+            height: 0,
+            group_id: Some(1),
+            reference_count: 0,
+            gmt_modified: None,
+        }
+    }
+
+    #[test]
+    fn content_is_hashed_as_hex_sha256() {
+        assert_eq!(
+            sha256_hex(b"abc"),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn files_with_the_recorded_hash_are_skipped() {
+        let manifest = manifest();
+        assert!(manifest.is_unchanged("Shoes/black.jpg", "aa"));
+        assert!(!manifest.is_unchanged("Shoes/black.jpg", "ab"));
+        assert!(!manifest.is_unchanged("Shoes/red.jpg", "aa"));
+    }
+
+    #[test]
+    fn same_content_is_reused_within_its_group_only() {
+        let manifest = manifest();
+        assert_eq!(manifest.find_content("bb", Some(1)).map(|e| e.id), Some(11));
+        assert!(manifest.find_content("bb", Some(2)).is_none());
+        assert!(manifest.find_content("bb", None).is_none());
+        assert_eq!(manifest.find_content("cc", None).map(|e| e.id), Some(12));
+    }
+
+    #[test]
+    fn changed_files_are_not_matched_by_name() {
+        let manifest = manifest();
+        assert!(!manifest.may_match_by_name("Shoes/black.jpg"));
+        assert!(manifest.may_match_by_name("Shoes/red.jpg"));
+
+        let images = [hosted(20, "red.jpg", 100), hosted(21, "red.jpg", 200)];
+        assert_eq!(
+            find_by_name_and_size(&images, "red.jpg", 200).map(|image| image.id),
+            Some(21)
+        );
+        assert!(find_by_name_and_size(&images, "red.jpg", 300).is_none());
+        assert!(find_by_name_and_size(&images, "Red.jpg", 100).is_none());
+    }
+
+    #[test]
+    fn entries_of_missing_files_are_pruned() {
+        let mut manifest = manifest();
+        let present = HashSet::from(["Shoes/white.jpg".to_string(), "new.jpg".to_string()]);
+
+        let removed = manifest.prune(&present);
+        assert_eq!(removed, vec!["Shoes/black.jpg", "logo.png"]);
+        assert_eq!(
+            manifest.files.keys().collect::<Vec<_>>(),
+            vec!["Shoes/white.jpg"]
+        );
+    }
+
+    #[tokio::test]
+    async fn manifest_is_replaced_through_a_temporary_file() {
+        let directory = std::env::temp_dir().join(format!(
+            "iop-client-manifest-{}-{}",
+            std::process::id(),
+            chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default()
+        ));
+        std::fs::create_dir_all(&directory).unwrap();
+        let path = directory.join("manifest.json");
+        std::fs::write(&path, b"{\"files\":{}}").unwrap();
+
+        manifest().save(&path).await.unwrap();
+
+        let saved = PhotobankManifest::read_json(std::fs::File::open(&path).unwrap()).unwrap();
+        assert_eq!(saved.files, manifest().files);
+        let leftovers: Vec<_> = std::fs::read_dir(&directory)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(leftovers, vec!["manifest.json"]);
+
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
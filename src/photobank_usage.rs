@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use futures::TryStreamExt;
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{limits, methods, urls},
+    photobank::PhotobankImageQuery,
+    IopClient,
+};
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankCapacityGetResponse {
+    alibaba_icbu_photobank_capacity_get_response: PhotobankCapacityResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct PhotobankCapacityResult {
+    capacity: PhotobankCapacity,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+/// The photo bank quota of the seller, in bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct PhotobankCapacity {
+    pub total_capacity: i64,
+    pub used_capacity: i64,
+}
+
+impl PhotobankCapacity {
+    /// Returns the bytes left before uploads are blocked.
+    pub fn remaining(&self) -> i64 {
+        (self.total_capacity - self.used_capacity).max(0)
+    }
+
+    /// Returns the used share of the quota, from `0.0` to `1.0`.
+    pub fn usage_ratio(&self) -> f64 {
+        if self.total_capacity <= 0 {
+            return 1.0;
+        }
+        (self.used_capacity as f64 / self.total_capacity as f64).clamp(0.0, 1.0)
+    }
+
+    /// Returns `true` if `bytes` more would still fit in the quota.
+    pub fn fits(&self, bytes: i64) -> bool {
+        bytes <= self.remaining()
+    }
+}
+
+/// The images stored in one photo bank group.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotobankGroupUsage {
+    /// The `PhotoAlbumGroup.id`, `None` for ungrouped images.
+    pub group_id: Option<i32>,
+    pub group_name: Option<String>,
+    pub image_count: usize,
+    pub bytes: i64,
+}
+
+/// The photo bank quota with the space taken by each group.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PhotobankUsage {
+    pub capacity: PhotobankCapacity,
+
+    /// Groups holding images, largest first.
+    pub groups: Vec<PhotobankGroupUsage>,
+
+    /// `true` if the quota is used beyond `PHOTOBANK_CAPACITY_WARNING_RATIO`.
+    pub near_capacity: bool,
+}
+
+impl IopClient {
+    /// 图片银行容量获取
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.photobank.capacity.get&methodType=GET/POST)
+    ///
+    /// Retrieves the photo bank quota and how much of it is used.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `PhotobankCapacity` if successful, or an error if the process fails.
+    pub async fn get_photo_bank_capacity(
+        &self,
+    ) -> Result<PhotobankCapacity, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("method", methods::ALIBABA_ICBU_PHOTOBANK_CAPACITY_GET);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------get_photo_bank_capacity-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<PhotobankCapacityGetResponse>(&body)?;
+
+        Ok(result.alibaba_icbu_photobank_capacity_get_response.capacity)
+    }
+
+    /// Summarizes the photo bank quota and the image count and bytes of each group.
+    ///
+    /// Every image is listed with `photo_bank_images`, so this is as slow as a full
+    /// listing. A warning is logged when the quota is nearly used up.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `PhotobankUsage` if successful, or an error if a
+    /// request fails.
+    pub async fn get_photo_bank_usage(&self) -> Result<PhotobankUsage, Box<dyn std::error::Error>> {
+        let capacity = self.get_photo_bank_capacity().await?;
+        let groups = self.list_photo_bank_groups(None).await?;
+
+        let mut usage: HashMap<Option<i32>, PhotobankGroupUsage> = HashMap::new();
+        let mut images = Box::pin(self.photo_bank_images(PhotobankImageQuery::default()));
+        while let Some(image) = images.try_next().await? {
+            let entry = usage
+                .entry(image.group_id)
+                .or_insert_with(|| PhotobankGroupUsage {
+                    group_id: image.group_id,
+                    group_name: groups
+                        .iter()
+                        .find(|group| Some(group.id) == image.group_id)
+                        .map(|group| group.name.clone()),
+                    image_count: 0,
+                    bytes: 0,
+                });
+            entry.image_count += 1;
+            entry.bytes += image.file_size;
+        }
+
+        let mut groups: Vec<PhotobankGroupUsage> = usage.into_values().collect();
+        groups.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.group_id.cmp(&b.group_id)));
+
+        let near_capacity = capacity.usage_ratio() >= limits::PHOTOBANK_CAPACITY_WARNING_RATIO;
+        if near_capacity {
+            warn!(
+                "Photo bank is {:.1}% full, {} bytes left",
+                capacity.usage_ratio() * 100.0,
+                capacity.remaining()
+            );
+        }
+
+        Ok(PhotobankUsage {
+            capacity,
+            groups,
+            near_capacity,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capacity(total_capacity: i64, used_capacity: i64) -> PhotobankCapacity {
+        PhotobankCapacity {
+            total_capacity,
+            used_capacity,
+        }
+    }
+
+    #[test]
+    fn remaining_space_is_never_negative() {
+        assert_eq!(capacity(1000, 250).remaining(), 750);
+        assert_eq!(capacity(1000, 1000).remaining(), 0);
+        assert_eq!(capacity(1000, 1200).remaining(), 0);
+        assert_eq!(capacity(0, 0).remaining(), 0);
+        assert_eq!(capacity(-5, 0).remaining(), 0);
+    }
+
+    #[test]
+    fn usage_ratio_stays_between_zero_and_one() {
+        assert_eq!(capacity(1000, 0).usage_ratio(), 0.0);
+        assert_eq!(capacity(1000, 250).usage_ratio(), 0.25);
+        assert_eq!(capacity(1000, 1000).usage_ratio(), 1.0);
+        assert_eq!(capacity(1000, 1200).usage_ratio(), 1.0);
+        assert_eq!(capacity(1000, -10).usage_ratio(), 0.0);
+    }
+
+    #[test]
+    fn quota_without_capacity_counts_as_full() {
+        for total_capacity in [0, -1] {
+            let quota = capacity(total_capacity, 0);
+            assert_eq!(quota.usage_ratio(), 1.0);
+            assert_eq!(quota.remaining(), 0);
+            assert!(quota.fits(0));
+            assert!(!quota.fits(1));
+        }
+    }
+
+    #[test]
+    fn fits_up_to_the_remaining_space() {
+        let quota = capacity(1000, 250);
+        assert!(quota.fits(0));
+        assert!(quota.fits(750));
+        assert!(!quota.fits(751));
+        assert!(!capacity(1000, 1200).fits(1));
+    }
+
+    #[test]
+    fn capacity_response_deserializes() {
+        let response: PhotobankCapacityGetResponse = serde_json::from_str(
+            r#"{"alibaba_icbu_photobank_capacity_get_response":{"capacity":{"total_capacity":5368709120,"used_capacity":1073741824},"request_id":"x"}}"#,
+        )
+        .unwrap();
+        let capacity = response
+            .alibaba_icbu_photobank_capacity_get_response
+            .capacity;
+        assert_eq!(capacity.remaining(), 4 * 1024 * 1024 * 1024);
+        assert_eq!(capacity.usage_ratio(), 0.2);
+    }
+}
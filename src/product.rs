@@ -0,0 +1,738 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDate;
+use futures::{stream, Stream, TryStreamExt};
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute_validation::{ProductAttributeValue, ProductAttributes},
+    constants::{limits, methods, urls},
+    model::{JavaList, NumberList, StringList},
+    product_category::string_enum,
+    product_group::empty_object_as_none,
+    IopClient,
+};
+
+string_enum! {
+    /// The review status of a product.
+    pub enum ProductStatus {
+        Approved => "approved",
+        Auditing => "auditing",
+        Rejected => "rejected",
+        /// Saved but not submitted for review.
+        Draft => "tbd",
+    }
+}
+
+/// Filters for listing products. Unset fields do not filter.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct ProductQuery {
+    /// Only products in this group, e.g. a `group_id` from `get_product_groups`.
+    pub group_id: Option<i32>,
+    /// Only products in this category.
+    pub category_id: Option<i32>,
+    pub status: Option<ProductStatus>,
+    /// Only products shown (`true`) or hidden (`false`) on the storefront.
+    pub displayed: Option<bool>,
+    /// Only products whose subject contains this text.
+    pub subject: Option<String>,
+    /// Only products modified on or after this day.
+    pub modified_from: Option<NaiveDate>,
+    /// Only products modified on or before this day.
+    pub modified_to: Option<NaiveDate>,
+}
+
+/// A product as returned by the product list API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductSummary {
+    pub id: i64,
+    pub subject: String,
+    pub category_id: i32,
+    pub group_id: Option<i32>,
+    pub status: Option<ProductStatus>,
+
+    /// `Y` if the product is shown on the storefront, `N` otherwise.
+    pub display: Option<String>,
+    pub language: Option<String>,
+    pub gmt_create: Option<String>,
+    pub gmt_modified: Option<String>,
+}
+
+/// One page of products.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductPage {
+    pub total_item: i32,
+    pub products: Vec<ProductSummary>,
+}
+
+/// A product with every detail, as returned by the product get API.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Product {
+    pub product_id: i64,
+    pub subject: String,
+    pub category_id: i32,
+    pub status: Option<ProductStatus>,
+
+    /// `Y` if the product is shown on the storefront, `N` otherwise.
+    pub display: Option<String>,
+    pub language: Option<String>,
+
+    /// The product group, e.g. a `group_id` from `get_product_groups`.
+    pub group_id: Option<i32>,
+
+    /// The groups from the top level down to `group_id`.
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub group_path: Option<NumberList<i32>>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub keywords: Option<StringList>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub main_image: Option<ProductImages>,
+
+    /// The detail page, as HTML.
+    pub description: Option<String>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub attributes: Option<JavaList<ProductAttribute>>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub skus: Option<JavaList<ProductSku>>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub trade_info: Option<ProductTradeInfo>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub packaging: Option<ProductPackaging>,
+
+    pub shipping_template_id: Option<i64>,
+    pub gmt_create: Option<String>,
+    pub gmt_modified: Option<String>,
+}
+
+/// The main images of a product, shown in the gallery.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductImages {
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub images: Option<StringList>,
+
+    /// `true` if the images are shown with the seller's watermark.
+    #[serde(default)]
+    pub watermark: bool,
+}
+
+/// A value of a product attribute.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductAttribute {
+    /// The `CategoryAttribute.attr_id`.
+    pub attribute_id: i32,
+    pub attribute_name: Option<String>,
+
+    /// The `AttributeValue.attr_value_id`, `None` for a custom value.
+    pub value_id: Option<i32>,
+    pub value_name: Option<String>,
+}
+
+/// A variant of a product.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductSku {
+    pub sku_id: Option<i64>,
+    pub sku_code: Option<String>,
+    pub price: Option<f64>,
+    pub inventory: Option<i64>,
+
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub attributes: Option<JavaList<ProductSkuAttribute>>,
+}
+
+/// One attribute value of a product variant.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductSkuAttribute {
+    pub attribute_id: i32,
+    pub value_id: Option<i32>,
+    pub value_name: Option<String>,
+
+    /// The image shown for this value.
+    pub image: Option<String>,
+}
+
+/// The pricing and order terms of a product.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductTradeInfo {
+    /// The minimum order quantity (MOQ), in `unit_type`.
+    pub min_order_quantity: Option<i32>,
+    pub unit_type: Option<String>,
+    pub currency: Option<String>,
+    pub min_price: Option<f64>,
+    pub max_price: Option<f64>,
+
+    /// Prices by order quantity, lowest quantity first.
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    pub ladder_prices: Option<JavaList<LadderPrice>>,
+}
+
+/// The unit price from a minimum order quantity on.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LadderPrice {
+    pub quantity: i32,
+    pub price: f64,
+}
+
+/// The packaging of one sales unit.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductPackaging {
+    /// Centimeters.
+    pub length: Option<f64>,
+    /// Centimeters.
+    pub width: Option<f64>,
+    /// Centimeters.
+    pub height: Option<f64>,
+    /// Kilograms.
+    pub gross_weight: Option<f64>,
+    pub package_type: Option<String>,
+}
+
+impl Product {
+    /// Returns the attribute values in the shape checked by `CategoryAttributeGroup::validate`.
+    pub fn attribute_values(&self) -> ProductAttributes {
+        let mut values: ProductAttributes = BTreeMap::new();
+        for attribute in self.attributes.iter().flat_map(|list| &list.java_util_list) {
+            let value = match (attribute.value_id, &attribute.value_name) {
+                (Some(value_id), _) => ProductAttributeValue::Id(value_id),
+                (None, Some(value_name)) => ProductAttributeValue::Custom(value_name.clone()),
+                (None, None) => continue,
+            };
+            values
+                .entry(attribute.attribute_id)
+                .or_default()
+                .push(value);
+        }
+        values
+    }
+
+    /// Returns every image URL the product uses: main images first, then SKU images,
+    /// then the `<img>` sources of the description.
+    pub fn image_urls(&self) -> Vec<String> {
+        let main = self
+            .main_image
+            .iter()
+            .filter_map(|main_image| main_image.images.as_ref())
+            .flat_map(|images| images.string.iter().cloned());
+        let skus = self
+            .skus
+            .iter()
+            .flat_map(|skus| &skus.java_util_list)
+            .filter_map(|sku| sku.attributes.as_ref())
+            .flat_map(|attributes| &attributes.java_util_list)
+            .filter_map(|attribute| attribute.image.clone());
+        let description = self
+            .description
+            .as_deref()
+            .map(description_image_urls)
+            .unwrap_or_default();
+
+        main.chain(skus).chain(description).collect()
+    }
+}
+
+/// Extracts the `src` of every `<img>` tag of an HTML fragment.
+fn description_image_urls(html: &str) -> Vec<String> {
+    // ASCII lowercasing keeps byte offsets, so positions found in `lower` index `html`.
+    let lower = html.to_ascii_lowercase();
+    let mut urls = Vec::new();
+    let mut rest = 0;
+
+    while let Some(start) = lower[rest..].find("<img") {
+        let tag_start = rest + start + "<img".len();
+        let tag_end = lower[tag_start..]
+            .find('>')
+            .map_or(lower.len(), |end| tag_start + end);
+        rest = tag_end;
+
+        // Skips other tags starting the same way, e.g. `<imgur-embed>`.
+        let tag = lower[tag_start..].chars().next();
+        if !tag.map_or(false, |c| c.is_whitespace() || c == '/' || c == '>') {
+            continue;
+        }
+
+        if let Some(url) =
+            attribute_value(&html[tag_start..tag_end], &lower[tag_start..tag_end], "src")
+        {
+            if !url.is_empty() {
+                urls.push(url.replace("&amp;", "&"));
+            }
+        }
+    }
+    urls
+}
+
+/// Returns the value of attribute `name` inside a tag, quoted or not.
+fn attribute_value<'a>(tag: &'a str, lower: &str, name: &str) -> Option<&'a str> {
+    let mut from = 0;
+    while let Some(found) = lower[from..].find(name) {
+        let start = from + found;
+        from = start + name.len();
+
+        // Skips longer names ending the same way, e.g. `data-src`.
+        let standalone = lower[..start]
+            .chars()
+            .next_back()
+            .map_or(true, char::is_whitespace);
+        let value = lower[from..].trim_start();
+        if !standalone || !value.starts_with('=') {
+            continue;
+        }
+
+        let value_start = lower.len() - value[1..].trim_start().len();
+        let value = &tag[value_start..];
+        return match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next(),
+            _ => value
+                .split(char::is_whitespace)
+                .next()
+                .map(|value| value.trim_end_matches('/')),
+        };
+    }
+    None
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductGetResponse {
+    alibaba_icbu_product_get_response: ProductGetResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductGetResult {
+    product: Product,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductListResponse {
+    alibaba_icbu_product_list_response: ProductListResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductListResult {
+    #[serde(default)]
+    total_item: i32,
+    #[serde(default, deserialize_with = "empty_object_as_none")]
+    products: Option<ProductBriefList>,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductBriefList {
+    alibaba_product_brief_response: Vec<ProductSummary>,
+}
+
+impl IopClient {
+    /// 商品列表查询
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.list&methodType=GET/POST)
+    ///
+    /// Lists one page of the seller's products matching `query`.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The filters to apply.
+    /// * `current_page` - The page to retrieve, starting at `1`.
+    /// * `page_size` - The number of products per page, at most `PRODUCT_LIST_PAGE_SIZE`.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ProductPage` if successful, or an error if the process fails.
+    pub async fn list_products(
+        &self,
+        query: &ProductQuery,
+        current_page: u32,
+        page_size: u32,
+    ) -> Result<ProductPage, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("current_page", current_page.to_string());
+        params.insert("page_size", page_size.to_string());
+        for (key, value) in query_params(query) {
+            params.insert(key, value);
+        }
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_LIST);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------list_products-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<ProductListResponse>(&body)?;
+        let result = result.alibaba_icbu_product_list_response;
+
+        Ok(ProductPage {
+            total_item: result.total_item,
+            products: result
+                .products
+                .map(|list| list.alibaba_product_brief_response)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Streams every product matching `query`, walking all pages.
+    ///
+    /// Pages are fetched lazily with `list_products` as the stream is polled.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - The filters to apply.
+    ///
+    /// # Returns
+    ///
+    /// A `Stream` of products, yielding an error and ending if a page request fails.
+    pub fn products(
+        &self,
+        query: ProductQuery,
+    ) -> impl Stream<Item = Result<ProductSummary, Box<dyn std::error::Error>>> + '_ {
+        let page_size = limits::PRODUCT_LIST_PAGE_SIZE;
+
+        stream::try_unfold(Some(1), move |current_page| {
+            let query = query.clone();
+            async move {
+                let current_page = match current_page {
+                    Some(current_page) => current_page,
+                    None => return Ok::<_, Box<dyn std::error::Error>>(None),
+                };
+
+                let page = self.list_products(&query, current_page, page_size).await?;
+                let next = next_page(current_page, page_size, &page);
+                Ok(Some((page.products, next)))
+            }
+        })
+        .map_ok(|products| stream::iter(products.into_iter().map(Ok)))
+        .try_flatten()
+    }
+
+    /// 商品详情查询
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.get&methodType=GET/POST)
+    ///
+    /// Retrieves the full record of a product.
+    ///
+    /// # Arguments
+    ///
+    /// * `product_id` - The `ProductSummary.id` of the product.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `Product` if successful, or an error if the process fails.
+    pub async fn get_product(
+        &self,
+        product_id: i64,
+    ) -> Result<Product, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("product_id", product_id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_GET);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------get_product-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<ProductGetResponse>(&body)?;
+
+        Ok(result.alibaba_icbu_product_get_response.product)
+    }
+}
+
+/// Returns the list API parameters filtering by the set fields of `query`.
+fn query_params(query: &ProductQuery) -> Vec<(&'static str, String)> {
+    let mut params = Vec::new();
+    if let Some(value) = query.group_id {
+        params.push(("group_id", value.to_string()));
+    }
+    if let Some(value) = query.category_id {
+        params.push(("category_id", value.to_string()));
+    }
+    if let Some(value) = &query.status {
+        params.push(("status", value.as_str().to_string()));
+    }
+    if let Some(value) = query.displayed {
+        params.push(("display", if value { "Y" } else { "N" }.to_string()));
+    }
+    if let Some(value) = &query.subject {
+        params.push(("subject", value.clone()));
+    }
+    if let Some(value) = query.modified_from {
+        params.push((
+            "gmt_modified_from",
+            value.format("%Y-%m-%d 00:00:00").to_string(),
+        ));
+    }
+    if let Some(value) = query.modified_to {
+        params.push((
+            "gmt_modified_to",
+            value.format("%Y-%m-%d 23:59:59").to_string(),
+        ));
+    }
+    params
+}
+
+/// Returns the page after `current_page`, or `None` if `page` was the last one.
+fn next_page(current_page: u32, page_size: u32, page: &ProductPage) -> Option<u32> {
+    let fetched = current_page as i64 * page_size as i64;
+    if page.products.len() < page_size as usize || fetched >= page.total_item as i64 {
+        None
+    } else {
+        Some(current_page + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn product(description: &str) -> Product {
+        serde_json::from_value(json!({
+            "product_id": 1,
+            "subject": "Boots",
+            "category_id": 100,
+            "status": "approved",
+            "main_image": { "images": { "string": [
+                "https://sc04.alicdn.com/kf/Hmain1.jpg",
+                "https://sc04.alicdn.com/kf/Hmain2.jpg",
+            ] }, "watermark": false },
+            "description": description,
+            "skus": { "java.util._list": [
+                { "sku_id": 5, "attributes": { "java.util._list": [
+                    { "attribute_id": 100, "value_id": 1, "image": "https://sc04.alicdn.com/kf/Hsku.jpg" },
+                    { "attribute_id": 300, "value_name": "XL" },
+                ] } },
+            ] },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn image_urls_include_description_images() {
+        let product = product(
+            r#"<p>Boots</p><IMG class="a" SRC="https://sc04.alicdn.com/kf/Hdesc1.jpg" alt="x"><img src='//sc04.alicdn.com/kf/Hdesc2.png'/>"#,
+        );
+
+        assert_eq!(
+            product.image_urls(),
+            vec![
+                "https://sc04.alicdn.com/kf/Hmain1.jpg",
+                "https://sc04.alicdn.com/kf/Hmain2.jpg",
+                "https://sc04.alicdn.com/kf/Hsku.jpg",
+                "https://sc04.alicdn.com/kf/Hdesc1.jpg",
+                "//sc04.alicdn.com/kf/Hdesc2.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn description_sources_are_parsed_leniently() {
+        let html = concat!(
+            r#"<img data-src="https://lazy/a.jpg" src = "https://x/b.jpg?w=1&amp;h=2">"#,
+            r#"<img src=https://x/c.jpg width=10>"#,
+            r#"<img src=https://x/d.jpg/>"#,
+            r#"<img alt="no source"><img src="">"#,
+            r#"<imgur-embed src="https://x/e.jpg">"#,
+        );
+
+        assert_eq!(
+            description_image_urls(html),
+            vec![
+                "https://x/b.jpg?w=1&h=2",
+                "https://x/c.jpg",
+                "https://x/d.jpg",
+            ]
+        );
+    }
+
+    #[test]
+    fn products_without_images_have_none() {
+        let product: Product = serde_json::from_value(json!({
+            "product_id": 2,
+            "subject": "Empty",
+            "category_id": 100,
+            "main_image": {},
+            "skus": {},
+        }))
+        .unwrap();
+        assert!(product.image_urls().is_empty());
+    }
+
+    #[test]
+    fn empty_query_adds_no_filters() {
+        assert!(query_params(&ProductQuery::default()).is_empty());
+    }
+
+    #[test]
+    fn query_params_format_every_filter() {
+        let query = ProductQuery {
+            group_id: Some(7),
+            category_id: Some(100),
+            status: Some(ProductStatus::Draft),
+            displayed: Some(false),
+            subject: Some("leather boots".to_string()),
+            modified_from: NaiveDate::from_ymd_opt(2024, 1, 5),
+            modified_to: NaiveDate::from_ymd_opt(2024, 2, 29),
+        };
+
+        assert_eq!(
+            query_params(&query),
+            vec![
+                ("group_id", "7".to_string()),
+                ("category_id", "100".to_string()),
+                ("status", "tbd".to_string()),
+                ("display", "N".to_string()),
+                ("subject", "leather boots".to_string()),
+                ("gmt_modified_from", "2024-01-05 00:00:00".to_string()),
+                ("gmt_modified_to", "2024-02-29 23:59:59".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn displayed_query_is_sent_as_y() {
+        let query = ProductQuery {
+            displayed: Some(true),
+            ..Default::default()
+        };
+        assert_eq!(query_params(&query), vec![("display", "Y".to_string())]);
+    }
+
+    fn page(total_item: i32, ids: std::ops::Range<i64>) -> ProductPage {
+        serde_json::from_value(json!({
+            "total_item": total_item,
+            "products": ids
+                .map(|id| json!({ "id": id, "subject": "Boots", "category_id": 100 }))
+                .collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn next_page_stops_on_short_or_last_pages() {
+        assert_eq!(next_page(1, 2, &page(5, 0..2)), Some(2));
+        assert_eq!(next_page(2, 2, &page(5, 2..4)), Some(3));
+        assert_eq!(next_page(3, 2, &page(5, 4..5)), None);
+        assert_eq!(next_page(2, 2, &page(4, 2..4)), None);
+        assert_eq!(next_page(1, 2, &page(0, 0..0)), None);
+    }
+
+    #[test]
+    fn list_response_without_products_is_empty() {
+        let response: ProductListResponse = serde_json::from_value(json!({
+            "alibaba_icbu_product_list_response": { "total_item": 0, "products": {} },
+        }))
+        .unwrap();
+        let result = response.alibaba_icbu_product_list_response;
+        assert_eq!(result.total_item, 0);
+        assert!(result.products.is_none());
+    }
+
+    #[test]
+    fn get_response_unwraps_list_wrappers() {
+        let response: ProductGetResponse = serde_json::from_value(json!({
+            "alibaba_icbu_product_get_response": {
+                "product": {
+                    "product_id": 3,
+                    "subject": "Boots",
+                    "category_id": 100,
+                    "status": "auditing",
+                    "group_path": { "number": [7, 8] },
+                    "keywords": { "string": ["boots", "leather"] },
+                    "skus": { "java.util._list": [
+                        { "sku_id": 5, "price": 12.5, "attributes": {} },
+                    ] },
+                    "trade_info": {
+                        "min_order_quantity": 2,
+                        "ladder_prices": { "java.util._list": [
+                            { "quantity": 2, "price": 15.0 },
+                            { "quantity": 10, "price": 12.5 },
+                        ] },
+                    },
+                },
+                "request_id": "abc",
+            },
+        }))
+        .unwrap();
+        let product = response.alibaba_icbu_product_get_response.product;
+
+        assert_eq!(product.status, Some(ProductStatus::Auditing));
+        assert_eq!(product.group_path.unwrap().number, vec![7, 8]);
+        assert_eq!(product.keywords.unwrap().string, vec!["boots", "leather"]);
+
+        let skus = product.skus.unwrap().java_util_list;
+        assert_eq!(skus.len(), 1);
+        assert_eq!(skus[0].price, Some(12.5));
+        assert!(skus[0].attributes.is_none());
+
+        let ladder_prices = product.trade_info.unwrap().ladder_prices.unwrap();
+        let quantities: Vec<i32> = ladder_prices
+            .java_util_list
+            .iter()
+            .map(|ladder_price| ladder_price.quantity)
+            .collect();
+        assert_eq!(quantities, vec![2, 10]);
+    }
+
+    #[test]
+    fn attribute_values_group_by_attribute() {
+        let product: Product = serde_json::from_value(json!({
+            "product_id": 3,
+            "subject": "Boots",
+            "category_id": 100,
+            "attributes": { "java.util._list": [
+                { "attribute_id": 1, "value_id": 10, "value_name": "Red" },
+                { "attribute_id": 1, "value_id": 11 },
+                { "attribute_id": 2, "value_name": "Cowhide" },
+                { "attribute_id": 3 },
+            ] },
+        }))
+        .unwrap();
+
+        let values = product.attribute_values();
+        assert_eq!(
+            values.get(&1).unwrap(),
+            &vec![ProductAttributeValue::Id(10), ProductAttributeValue::Id(11)]
+        );
+        assert_eq!(
+            values.get(&2).unwrap(),
+            &vec![ProductAttributeValue::Custom("Cowhide".to_string())]
+        );
+        assert!(!values.contains_key(&3));
+    }
+
+    #[test]
+    fn empty_or_missing_wrappers_are_none() {
+        let product: Product = serde_json::from_value(json!({
+            "product_id": 4,
+            "subject": "Boots",
+            "category_id": 100,
+            "group_path": {},
+            "keywords": null,
+            "attributes": {},
+            "trade_info": {},
+        }))
+        .unwrap();
+
+        assert!(product.group_path.is_none());
+        assert!(product.keywords.is_none());
+        assert!(product.attributes.is_none());
+        assert!(product.skus.is_none());
+        assert!(product.trade_info.is_none());
+        assert!(product.packaging.is_none());
+        assert!(product.attribute_values().is_empty());
+    }
+
+    #[test]
+    fn wrappers_with_unexpected_keys_are_rejected() {
+        let product = serde_json::from_value::<Product>(json!({
+            "product_id": 4,
+            "subject": "Boots",
+            "category_id": 100,
+            "keywords": { "number": [1] },
+        }));
+        assert!(product.is_err());
+    }
+}
@@ -1,9 +1,7 @@
 use crate::constants::{methods, urls};
 
-use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
-
 use log::info;
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::IopClient;
 
@@ -19,7 +17,7 @@ pub struct NewCategoryGroup {
     pub category: NewCategory,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NewCategory {
     pub leaf_category: bool,
     pub cn_name: Option<String>,
@@ -34,7 +32,7 @@ pub struct NewCategory {
     pub parent_ids: Option<NewCategoryChildId>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct NewCategoryChildId {
     pub number: Vec<String>,
 }
@@ -59,20 +57,136 @@ pub struct CategoryAttributes {
 #[derive(Serialize, Deserialize, Debug)]
 pub struct CategoryAttribute {
     pub sku_attribute: bool,
-    pub show_type: String,
+    pub show_type: ShowType,
     pub customize_image: bool,
     pub car_model: bool,
-    pub value_type: String,
+    pub value_type: ValueType,
     pub customize_value: bool,
 
     #[serde(deserialize_with = "empty_object_as_none")]
     pub attribute_values: Option<AttributeValues>,
-    pub input_type: String,
+    pub input_type: InputType,
     pub en_name: String,
     pub required: bool,
     pub attr_id: i32,
 }
 
+/// Declares an enum mirroring a string field of the API, keeping unrecognized
+/// values in `Unknown` so new values from Alibaba do not break deserialization.
+macro_rules! string_enum {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident {
+            $($(#[$variant_meta:meta])* $variant:ident => $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+        #[serde(from = "String", into = "String")]
+        pub enum $name {
+            $($(#[$variant_meta])* $variant,)*
+            /// A value this crate does not know about yet.
+            Unknown(String),
+        }
+
+        impl $name {
+            /// Returns the value as sent by the API.
+            pub fn as_str(&self) -> &str {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(value) => value,
+                }
+            }
+        }
+
+        impl From<String> for $name {
+            fn from(value: String) -> Self {
+                match value.as_str() {
+                    $($value => $name::$variant,)*
+                    _ => $name::Unknown(value),
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                match value {
+                    $name::Unknown(value) => value,
+                    known => known.as_str().to_string(),
+                }
+            }
+        }
+
+        impl std::fmt::Display for $name {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+    };
+}
+
+pub(crate) use string_enum;
+
+string_enum! {
+    /// How an attribute is displayed, i.e. which form control renders it.
+    pub enum ShowType {
+        /// A free text field.
+        Input => "input",
+        /// A drop-down list allowing one value.
+        ListBox => "list_box",
+        /// A set of check boxes allowing several values.
+        CheckBox => "check_box",
+        /// A table of grouped sub-attributes.
+        GroupTable => "group_table",
+    }
+}
+
+string_enum! {
+    /// How values of an attribute are entered.
+    pub enum InputType {
+        /// Typed in by the seller.
+        Input => "input",
+        /// Exactly one value picked from `attribute_values`.
+        SingleSelect => "single_select",
+        /// Any number of values picked from `attribute_values`.
+        MultiSelect => "multi_select",
+    }
+}
+
+string_enum! {
+    /// The type of the values of an attribute.
+    pub enum ValueType {
+        String => "string",
+        Number => "number",
+    }
+}
+
+impl CategoryAttribute {
+    /// Returns `true` if the attribute accepts more than one value.
+    pub fn is_multi_valued(&self) -> bool {
+        self.show_type == ShowType::CheckBox || self.input_type == InputType::MultiSelect
+    }
+
+    /// Returns `true` if the schema flags the attribute as hierarchical, i.e. its
+    /// values have child values fetched with `get_level_attribute_values`.
+    ///
+    /// Only `car_model` attributes are flagged; other cascades, such as brand to
+    /// model, are expanded on request through `expand_attribute_cascade` or the
+    /// `attr_ids` of `expand_category_cascades`.
+    pub fn is_hierarchical(&self) -> bool {
+        self.car_model
+    }
+
+    /// Returns `true` if values are picked from `attribute_values` rather than typed in.
+    pub fn is_enumerated(&self) -> bool {
+        matches!(self.show_type, ShowType::ListBox | ShowType::CheckBox)
+            || matches!(
+                self.input_type,
+                InputType::SingleSelect | InputType::MultiSelect
+            )
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct AttributeValues {
     pub attribute_value: Vec<AttributeValue>,
@@ -132,21 +246,15 @@ impl IopClient {
         &self,
         cat_id: i32,
     ) -> Result<NewCategory, Box<dyn std::error::Error>> {
-        let mut map = HashMap::new();
-        map.insert("cat_id".to_string(), format!("{}", cat_id));
-        map.insert(
-            "method".to_string(),
-            methods::ALIBABA_ICBU_CATEGORY_GET_NEW.to_string(),
-        );
+        let mut params = self.build_signed_params().await;
+        params.insert("cat_id", cat_id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_CATEGORY_GET_NEW);
 
-        let params = self.build_request_params(map).await;
-
-        let hash = self.generate_sign(None, params.clone());
-        let url = self.generate_url(urls::BASE_SYNC_URL.to_string(), params.clone(), hash);
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
         info!("--------list_product_categories-------- url: {:#?}", url);
 
-        let response = self.client.get(&url).send().await?;
-        let result = response.json::<NewCategoryResponse>().await?;
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<NewCategoryResponse>(&body)?;
 
         Ok(result.alibaba_icbu_category_get_new_response.category)
     }
@@ -170,25 +278,105 @@ impl IopClient {
     /// for the request, and the request is sent to the API endpoint. Upon successful completion,
     /// the category attributes are returned.
     pub async fn get_category_attributes(
-        self,
+        &self,
         cat_id: i32,
     ) -> Result<CategoryAttributeGroup, Box<dyn std::error::Error>> {
-        let mut map = HashMap::new();
-        map.insert("cat_id".to_string(), format!("{}", cat_id));
-        map.insert(
-            "method".to_string(),
-            methods::ALIBABA_ICBU_CATEGORY_ATTRIBUTE_GET.to_string(),
-        );
-
-        let params = self.build_request_params(map).await;
+        let mut params = self.build_signed_params().await;
+        params.insert("cat_id", cat_id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_CATEGORY_ATTRIBUTE_GET);
 
-        let hash = self.generate_sign(None, params.clone());
-        let url = self.generate_url(urls::BASE_SYNC_URL.to_string(), params.clone(), hash);
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
         info!("--------get_category_attributes-------- url: {:#?}", url);
 
-        let response = self.client.get(&url).send().await?;
-        let result = response.json::<CategoryAttributeGetResponse>().await?;
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<CategoryAttributeGetResponse>(&body)?;
 
         Ok(result.alibaba_icbu_category_attribute_get_response)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::product::ProductStatus;
+    use serde_json::json;
+
+    #[test]
+    fn known_values_round_trip() {
+        let show_type: ShowType = serde_json::from_value(json!("check_box")).unwrap();
+        assert_eq!(show_type, ShowType::CheckBox);
+        assert_eq!(
+            serde_json::to_value(&show_type).unwrap(),
+            json!("check_box")
+        );
+
+        let input_type: InputType = serde_json::from_value(json!("multi_select")).unwrap();
+        assert_eq!(input_type, InputType::MultiSelect);
+        assert_eq!(
+            serde_json::to_value(&input_type).unwrap(),
+            json!("multi_select")
+        );
+
+        let value_type: ValueType = serde_json::from_value(json!("number")).unwrap();
+        assert_eq!(value_type, ValueType::Number);
+        assert_eq!(serde_json::to_value(&value_type).unwrap(), json!("number"));
+
+        let status: ProductStatus = serde_json::from_value(json!("tbd")).unwrap();
+        assert_eq!(status, ProductStatus::Draft);
+        assert_eq!(serde_json::to_value(&status).unwrap(), json!("tbd"));
+        assert_eq!(status.to_string(), "tbd");
+    }
+
+    #[test]
+    fn unknown_values_are_kept_verbatim() {
+        let show_type: ShowType = serde_json::from_value(json!("color_picker")).unwrap();
+        assert_eq!(show_type, ShowType::Unknown("color_picker".to_string()));
+        assert_eq!(
+            serde_json::to_value(&show_type).unwrap(),
+            json!("color_picker")
+        );
+
+        let input_type: InputType = serde_json::from_value(json!("cascade")).unwrap();
+        assert_eq!(input_type, InputType::Unknown("cascade".to_string()));
+        assert_eq!(serde_json::to_value(&input_type).unwrap(), json!("cascade"));
+
+        let value_type: ValueType = serde_json::from_value(json!("date")).unwrap();
+        assert_eq!(value_type, ValueType::Unknown("date".to_string()));
+        assert_eq!(value_type.as_str(), "date");
+
+        let status: ProductStatus = serde_json::from_value(json!("frozen")).unwrap();
+        assert_eq!(status, ProductStatus::Unknown("frozen".to_string()));
+        assert_eq!(serde_json::to_value(&status).unwrap(), json!("frozen"));
+    }
+
+    #[test]
+    fn values_are_case_sensitive() {
+        let show_type: ShowType = serde_json::from_value(json!("Input")).unwrap();
+        assert_eq!(show_type, ShowType::Unknown("Input".to_string()));
+    }
+
+    #[test]
+    fn attribute_with_unknown_types_deserializes() {
+        let attribute: CategoryAttribute = serde_json::from_value(json!({
+            "attr_id": 1,
+            "en_name": "Material",
+            "sku_attribute": false,
+            "required": true,
+            "show_type": "rich_text",
+            "input_type": "cascade",
+            "value_type": "date",
+            "customize_image": false,
+            "customize_value": true,
+            "car_model": false,
+            "attribute_values": {},
+        }))
+        .unwrap();
+
+        assert_eq!(
+            attribute.show_type,
+            ShowType::Unknown("rich_text".to_string())
+        );
+        assert!(!attribute.is_enumerated());
+        assert!(!attribute.is_multi_valued());
+    }
+}
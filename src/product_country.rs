@@ -1,6 +1,5 @@
 use log::info;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 use crate::{
     constants::{methods, urls},
@@ -74,22 +73,18 @@ impl IopClient {
         &self,
         _language: Option<String>,
     ) -> Result<ProductCountryDto, Box<dyn std::error::Error>> {
-        let mut map = HashMap::new();
-        map.insert("country_request".to_string(), "{}".to_string());
-        map.insert(
-            "method".to_string(),
-            methods::ALIBABA_ICBU_PRODUCT_COUNTRY_GETCOUNTRYLIST.to_string(),
+        let mut params = self.build_signed_params().await;
+        params.insert("country_request", "{}");
+        params.insert(
+            "method",
+            methods::ALIBABA_ICBU_PRODUCT_COUNTRY_GETCOUNTRYLIST,
         );
-        let params = self.build_request_params(map).await;
 
-        let hash = self.generate_sign(None, params.clone());
-        let url = self.generate_url(urls::BASE_SYNC_URL.to_string(), params.clone(), hash);
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
         info!("--------list_product_countries-------- url: {:#?}", url);
 
-        let response = self.client.get(&url).send().await?;
-        let result = response
-            .json::<ProductCountryGetCountryListResponse>()
-            .await?;
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<ProductCountryGetCountryListResponse>(&body)?;
 
         Ok(result.response.data)
     }
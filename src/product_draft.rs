@@ -0,0 +1,748 @@
+use std::{collections::BTreeMap, fmt};
+
+use log::info;
+use reqwest::header::CONTENT_TYPE;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute_validation::{AttributeViolation, ProductAttributeValue, ProductAttributes},
+    constants::{limits, methods, urls},
+    product::{ProductAttribute, ProductPackaging, ProductSkuAttribute, ProductTradeInfo},
+    product_category::CategoryAttributeGroup,
+    sku::{Sku, SkuBuilder, SkuValue, SkuViolation},
+    IopClient, SignedParams, SigningKey,
+};
+
+/// The price and stock of one SKU.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SkuDetail {
+    pub price: f64,
+    pub inventory: Option<i64>,
+    pub sku_code: Option<String>,
+}
+
+/// A reason a draft cannot be published.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum DraftViolation {
+    EmptySubject,
+    SubjectTooLong {
+        length: usize,
+        max: usize,
+    },
+    MissingImage,
+    TooManyImages {
+        count: usize,
+        max: usize,
+    },
+    TooManyKeywords {
+        count: usize,
+        max: usize,
+    },
+    /// Neither a SKU detail nor `trade_info.min_price` gives the SKU a price.
+    MissingPrice {
+        sku_key: Option<String>,
+    },
+    /// A SKU detail was set for a combination the SKU attributes do not produce.
+    UnknownSku {
+        sku_key: String,
+    },
+    Attribute(AttributeViolation),
+    Sku(SkuViolation),
+}
+
+impl fmt::Display for DraftViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DraftViolation::EmptySubject => write!(f, "Subject is required"),
+            DraftViolation::SubjectTooLong { length, max } => {
+                write!(
+                    f,
+                    "Subject has {length} characters, at most {max} are allowed"
+                )
+            }
+            DraftViolation::MissingImage => write!(f, "At least one image is required"),
+            DraftViolation::TooManyImages { count, max } => {
+                write!(f, "{count} images given, at most {max} are allowed")
+            }
+            DraftViolation::TooManyKeywords { count, max } => {
+                write!(f, "{count} keywords given, at most {max} are allowed")
+            }
+            DraftViolation::MissingPrice {
+                sku_key: Some(sku_key),
+            } => {
+                write!(f, "SKU {sku_key} has no price")
+            }
+            DraftViolation::MissingPrice { sku_key: None } => write!(f, "Product has no price"),
+            DraftViolation::UnknownSku { sku_key } => {
+                write!(
+                    f,
+                    "SKU {sku_key} is not a combination of the SKU attributes"
+                )
+            }
+            DraftViolation::Attribute(violation) => violation.fmt(f),
+            DraftViolation::Sku(violation) => violation.fmt(f),
+        }
+    }
+}
+
+/// The violations that stopped `publish_product`.
+#[derive(Debug, Clone)]
+pub struct ProductDraftError {
+    pub violations: Vec<DraftViolation>,
+}
+
+impl fmt::Display for ProductDraftError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Product draft is invalid:")?;
+        for violation in &self.violations {
+            write!(f, " {violation};")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ProductDraftError {}
+
+/// Builds a product to publish with `publish_product`.
+///
+/// Everything but the subject and category is set with the builder methods; the
+/// draft is checked against the category attributes before it is sent.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductDraft {
+    subject: String,
+    category_id: i32,
+    group_id: Option<i32>,
+    attributes: ProductAttributes,
+    sku_attributes: BTreeMap<i32, Vec<SkuValue>>,
+    sku_details: BTreeMap<String, SkuDetail>,
+    images: Vec<String>,
+    description: Option<String>,
+    keywords: Vec<String>,
+    trade_info: Option<ProductTradeInfo>,
+    packaging: Option<ProductPackaging>,
+    shipping_template_id: Option<i64>,
+}
+
+impl ProductDraft {
+    /// Creates a draft.
+    ///
+    /// # Arguments
+    ///
+    /// * `subject` - The product title.
+    /// * `category_id` - A leaf category of the new category tree.
+    pub fn new(subject: impl Into<String>, category_id: i32) -> Self {
+        ProductDraft {
+            subject: subject.into(),
+            category_id,
+            group_id: None,
+            attributes: BTreeMap::new(),
+            sku_attributes: BTreeMap::new(),
+            sku_details: BTreeMap::new(),
+            images: Vec::new(),
+            description: None,
+            keywords: Vec::new(),
+            trade_info: None,
+            packaging: None,
+            shipping_template_id: None,
+        }
+    }
+
+    /// Places the product in a product group.
+    pub fn group(mut self, group_id: i32) -> Self {
+        self.group_id = Some(group_id);
+        self
+    }
+
+    /// Sets the values of a product attribute, replacing previous ones.
+    pub fn attribute(mut self, attr_id: i32, values: Vec<ProductAttributeValue>) -> Self {
+        self.attributes.insert(attr_id, values);
+        self
+    }
+
+    /// Sets the values of a SKU attribute, as for `SkuBuilder::attribute`.
+    pub fn sku_attribute(mut self, attr_id: i32, values: Vec<SkuValue>) -> Self {
+        self.sku_attributes.insert(attr_id, values);
+        self
+    }
+
+    /// Sets the price and stock of one SKU.
+    ///
+    /// # Arguments
+    ///
+    /// * `sku_key` - The `Sku.key` of the combination, e.g. `100:200;300:c:Navy Blue`.
+    /// * `detail` - The price and stock.
+    pub fn sku_detail(mut self, sku_key: impl Into<String>, detail: SkuDetail) -> Self {
+        self.sku_details.insert(sku_key.into(), detail);
+        self
+    }
+
+    /// Adds a main image, e.g. the `url` of an `UploadedPhoto`. The first is the cover.
+    pub fn image(mut self, url: impl Into<String>) -> Self {
+        self.images.push(url.into());
+        self
+    }
+
+    /// Sets the detail page, as HTML.
+    pub fn description(mut self, description: impl Into<String>) -> Self {
+        self.description = Some(description.into());
+        self
+    }
+
+    /// Adds a search keyword.
+    pub fn keyword(mut self, keyword: impl Into<String>) -> Self {
+        self.keywords.push(keyword.into());
+        self
+    }
+
+    /// Sets the MOQ and prices. `min_price` is used for SKUs without a detail.
+    pub fn trade_info(mut self, trade_info: ProductTradeInfo) -> Self {
+        self.trade_info = Some(trade_info);
+        self
+    }
+
+    /// Sets the packaging of one sales unit.
+    pub fn packaging(mut self, packaging: ProductPackaging) -> Self {
+        self.packaging = Some(packaging);
+        self
+    }
+
+    /// Sets the shipping template.
+    pub fn shipping_template(mut self, shipping_template_id: i64) -> Self {
+        self.shipping_template_id = Some(shipping_template_id);
+        self
+    }
+
+    /// Checks the draft against the product limits and the category attributes.
+    ///
+    /// Product attributes go through `CategoryAttributeGroup::validate` and SKU
+    /// attributes through `SkuBuilder`, so required SKU attributes are only expected
+    /// among the SKU attributes.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The category attributes, as returned by `get_category_attributes`.
+    ///
+    /// # Returns
+    ///
+    /// Every violation found, empty if the draft can be published.
+    pub fn validate(&self, schema: &CategoryAttributeGroup) -> Vec<DraftViolation> {
+        match self.build_skus(schema) {
+            Ok(_) => Vec::new(),
+            Err(violations) => violations,
+        }
+    }
+
+    fn sku_builder<'a>(&self, schema: &'a CategoryAttributeGroup) -> SkuBuilder<'a> {
+        self.sku_attributes
+            .iter()
+            .fold(SkuBuilder::new(schema), |builder, (attr_id, values)| {
+                builder.attribute(*attr_id, values.clone())
+            })
+    }
+
+    /// Validates the draft and generates its SKUs.
+    fn build_skus(&self, schema: &CategoryAttributeGroup) -> Result<Vec<Sku>, Vec<DraftViolation>> {
+        let mut violations = Vec::new();
+
+        let length = self.subject.trim().chars().count();
+        if length == 0 {
+            violations.push(DraftViolation::EmptySubject);
+        } else if length > limits::PRODUCT_SUBJECT_MAX_CHARS {
+            violations.push(DraftViolation::SubjectTooLong {
+                length,
+                max: limits::PRODUCT_SUBJECT_MAX_CHARS,
+            });
+        }
+
+        if self.images.is_empty() {
+            violations.push(DraftViolation::MissingImage);
+        } else if self.images.len() > limits::PRODUCT_IMAGES_MAX {
+            violations.push(DraftViolation::TooManyImages {
+                count: self.images.len(),
+                max: limits::PRODUCT_IMAGES_MAX,
+            });
+        }
+
+        if self.keywords.len() > limits::PRODUCT_KEYWORDS_MAX {
+            violations.push(DraftViolation::TooManyKeywords {
+                count: self.keywords.len(),
+                max: limits::PRODUCT_KEYWORDS_MAX,
+            });
+        }
+
+        for violation in schema.validate(&self.attributes) {
+            if let AttributeViolation::MissingRequired { attr_id, .. } = &violation {
+                let sku_attribute = schema
+                    .attribute(*attr_id)
+                    .map_or(false, |attribute| attribute.sku_attribute);
+                if sku_attribute {
+                    continue;
+                }
+            }
+            violations.push(DraftViolation::Attribute(violation));
+        }
+
+        let skus = match self.sku_builder(schema).build() {
+            Ok(skus) => skus,
+            Err(sku_violations) => {
+                violations.extend(sku_violations.into_iter().map(DraftViolation::Sku));
+                return Err(violations);
+            }
+        };
+
+        for sku_key in self.sku_details.keys() {
+            if !skus.iter().any(|sku| &sku.key == sku_key) {
+                violations.push(DraftViolation::UnknownSku {
+                    sku_key: sku_key.clone(),
+                });
+            }
+        }
+
+        let min_price = self
+            .trade_info
+            .as_ref()
+            .and_then(|trade_info| trade_info.min_price);
+        if min_price.is_none() {
+            if skus.is_empty() {
+                violations.push(DraftViolation::MissingPrice { sku_key: None });
+            }
+            for sku in &skus {
+                if !self.sku_details.contains_key(&sku.key) {
+                    violations.push(DraftViolation::MissingPrice {
+                        sku_key: Some(sku.key.clone()),
+                    });
+                }
+            }
+        }
+
+        if violations.is_empty() {
+            Ok(skus)
+        } else {
+            Err(violations)
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct ProductAddRequest<'a> {
+    subject: &'a str,
+    category_id: i32,
+    group_id: Option<i32>,
+    description: Option<&'a str>,
+    keywords: &'a [String],
+    main_image: MainImageRequest<'a>,
+    attributes: Vec<ProductAttribute>,
+    skus: Vec<SkuRequest>,
+    trade_info: Option<&'a ProductTradeInfo>,
+    packaging: Option<&'a ProductPackaging>,
+    shipping_template_id: Option<i64>,
+}
+
+#[derive(Serialize, Debug)]
+struct MainImageRequest<'a> {
+    images: &'a [String],
+}
+
+#[derive(Serialize, Debug)]
+struct SkuRequest {
+    attributes: Vec<ProductSkuAttribute>,
+    price: Option<f64>,
+    inventory: Option<i64>,
+    sku_code: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductAddResponse {
+    alibaba_icbu_product_add_response: ProductAddResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductAddResult {
+    product_id: i64,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+impl IopClient {
+    /// 发布商品
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.add&methodType=POST)
+    ///
+    /// Publishes a product.
+    ///
+    /// The category attributes are fetched with `get_category_attributes` and the draft
+    /// is validated against them before anything is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `draft` - The product to publish.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new product ID if successful, a `ProductDraftError`
+    /// listing the violations if the draft is invalid, or an error if a request fails.
+    pub async fn publish_product(
+        &self,
+        draft: &ProductDraft,
+    ) -> Result<i64, Box<dyn std::error::Error>> {
+        let schema = self.get_category_attributes(draft.category_id).await?;
+        let skus = match draft.build_skus(&schema) {
+            Ok(skus) => skus,
+            Err(violations) => return Err(Box::new(ProductDraftError { violations })),
+        };
+
+        let params = self.build_signed_params().await;
+        let (url, body) = product_add_form(params, &self.signing_key, draft, skus)?;
+        info!("--------publish_product-------- url: {:#?}", url);
+
+        let response = self
+            .client
+            .post(&url)
+            .header(
+                CONTENT_TYPE,
+                "application/x-www-form-urlencoded;charset=utf-8",
+            )
+            .body(body)
+            .send()
+            .await?;
+        let result = response.json::<ProductAddResponse>().await?;
+
+        Ok(result.alibaba_icbu_product_add_response.product_id)
+    }
+}
+
+/// Builds the signed `alibaba.icbu.product.add` request.
+///
+/// The product JSON carries the description and every SKU and attribute, far more
+/// than a URL can hold, so it is sent in the form body.
+fn product_add_form(
+    mut params: SignedParams<'_>,
+    key: &SigningKey,
+    draft: &ProductDraft,
+    skus: Vec<Sku>,
+) -> Result<(String, String), serde_json::Error> {
+    let min_price = draft
+        .trade_info
+        .as_ref()
+        .and_then(|trade_info| trade_info.min_price);
+
+    let request = ProductAddRequest {
+        subject: draft.subject.trim(),
+        category_id: draft.category_id,
+        group_id: draft.group_id,
+        description: draft.description.as_deref(),
+        keywords: &draft.keywords,
+        main_image: MainImageRequest {
+            images: &draft.images,
+        },
+        attributes: draft
+            .attributes
+            .iter()
+            .flat_map(|(attr_id, values)| {
+                values.iter().map(move |value| {
+                    let (value_id, value_name) = split_value(value);
+                    ProductAttribute {
+                        attribute_id: *attr_id,
+                        attribute_name: None,
+                        value_id,
+                        value_name,
+                    }
+                })
+            })
+            .collect(),
+        skus: skus
+            .into_iter()
+            .map(|sku| {
+                let detail = draft.sku_details.get(&sku.key);
+                SkuRequest {
+                    attributes: sku
+                        .values
+                        .into_iter()
+                        .map(|value| {
+                            let (value_id, value_name) = split_value(&value.value);
+                            ProductSkuAttribute {
+                                attribute_id: value.attr_id,
+                                value_id,
+                                value_name,
+                                image: value.image,
+                            }
+                        })
+                        .collect(),
+                    price: detail.map(|detail| detail.price).or(min_price),
+                    inventory: detail.and_then(|detail| detail.inventory),
+                    sku_code: detail.and_then(|detail| detail.sku_code.clone()),
+                }
+            })
+            .collect(),
+        trade_info: draft.trade_info.as_ref(),
+        packaging: draft.packaging.as_ref(),
+        shipping_template_id: draft.shipping_template_id,
+    };
+    params.insert("product", serde_json::to_string(&request)?);
+    params.insert("method", methods::ALIBABA_ICBU_PRODUCT_ADD);
+
+    Ok(params.to_form(key, urls::BASE_SYNC_URL, None))
+}
+
+fn split_value(value: &ProductAttributeValue) -> (Option<i32>, Option<String>) {
+    match value {
+        ProductAttributeValue::Id(value_id) => (Some(*value_id), None),
+        ProductAttributeValue::Custom(value) => (None, Some(value.clone())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use urlencoding::decode;
+
+    use super::*;
+
+    const COLOR: i32 = 100;
+    const SIZE: i32 = 300;
+
+    fn schema() -> CategoryAttributeGroup {
+        let sku_attribute = |attr_id: i32, en_name: &str| {
+            serde_json::json!({
+                "attr_id": attr_id,
+                "en_name": en_name,
+                "sku_attribute": true,
+                "required": attr_id == COLOR,
+                "show_type": "input",
+                "input_type": "input",
+                "value_type": "string",
+                "customize_image": false,
+                "customize_value": true,
+                "car_model": false,
+                "attribute_values": {},
+            })
+        };
+
+        serde_json::from_value(serde_json::json!({
+            "attributes": {
+                "attribute": [sku_attribute(COLOR, "Color"), sku_attribute(SIZE, "Size")],
+            },
+        }))
+        .unwrap()
+    }
+
+    fn custom_values(prefix: &str, count: usize) -> Vec<SkuValue> {
+        (0..count)
+            .map(|index| SkuValue::new(ProductAttributeValue::Custom(format!("{prefix} {index}"))))
+            .collect()
+    }
+
+    fn query_param<'u>(url: &'u str, name: &str) -> Option<&'u str> {
+        url.split_once('?')?
+            .1
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+    }
+
+    #[test]
+    fn large_product_is_sent_in_body() {
+        let schema = schema();
+        let description = format!("<p>{}</p>", "Waterproof leather boots. ".repeat(2000));
+        let draft = ProductDraft::new("Leather boots", 1)
+            .image("https://sc04.alicdn.com/kf/Hboots.jpg")
+            .description(description.as_str())
+            .sku_attribute(COLOR, custom_values("Color", 10))
+            .sku_attribute(SIZE, custom_values("Size", 10))
+            .trade_info(ProductTradeInfo {
+                min_order_quantity: Some(1),
+                unit_type: None,
+                currency: Some("USD".to_string()),
+                min_price: Some(49.9),
+                max_price: None,
+                ladder_prices: None,
+            });
+        let skus = draft.build_skus(&schema).unwrap();
+        assert_eq!(skus.len(), 100);
+
+        let key = SigningKey::new("secret");
+        let mut params = SignedParams::new();
+        params.insert("app_key", "500000");
+        params.insert("access_token", "token");
+        params.insert("timestamp", "1735689600000");
+
+        let (url, body) = product_add_form(params.clone(), &key, &draft, skus).unwrap();
+
+        assert!(url.len() < 512, "URL has {} bytes", url.len());
+        assert_eq!(query_param(&url, "product"), None);
+        assert_eq!(
+            query_param(&url, "method"),
+            Some(methods::ALIBABA_ICBU_PRODUCT_ADD)
+        );
+
+        let raw = decode(body.strip_prefix("product=").unwrap()).unwrap();
+        let product: serde_json::Value = serde_json::from_str(&raw).unwrap();
+        assert_eq!(product["description"], description.as_str());
+        assert_eq!(product["skus"].as_array().unwrap().len(), 100);
+
+        params.insert("product", raw);
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_ADD);
+        assert_eq!(
+            query_param(&url, "sign"),
+            Some(params.sign(&key, None).as_str())
+        );
+    }
+
+    fn detail(price: f64) -> SkuDetail {
+        SkuDetail {
+            price,
+            inventory: None,
+            sku_code: None,
+        }
+    }
+
+    /// A draft with two colors, each with a price.
+    fn draft() -> ProductDraft {
+        ProductDraft::new("Leather boots", 1)
+            .image("https://sc04.alicdn.com/kf/Hboots.jpg")
+            .sku_attribute(COLOR, custom_values("Color", 2))
+            .sku_detail("100:c:Color 0", detail(49.9))
+            .sku_detail("100:c:Color 1", detail(59.9))
+    }
+
+    #[test]
+    fn valid_draft_has_no_violations() {
+        assert!(draft().validate(&schema()).is_empty());
+    }
+
+    #[test]
+    fn required_sku_attributes_are_not_expected_among_product_attributes() {
+        let violations = draft().validate(&schema());
+        assert!(!violations
+            .iter()
+            .any(|violation| matches!(violation, DraftViolation::Attribute(_))));
+    }
+
+    #[test]
+    fn missing_required_sku_attribute_is_reported_once() {
+        let draft = ProductDraft::new("Leather boots", 1)
+            .image("https://sc04.alicdn.com/kf/Hboots.jpg")
+            .sku_attribute(SIZE, custom_values("Size", 1))
+            .sku_detail("300:c:Size 0", detail(49.9));
+
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::Sku(SkuViolation::MissingRequired {
+                attr_id: COLOR,
+                name: "Color".to_string(),
+            })]
+        );
+    }
+
+    #[test]
+    fn blank_subject_is_empty() {
+        let mut draft = draft();
+        draft.subject = "  ".to_string();
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::EmptySubject]
+        );
+    }
+
+    #[test]
+    fn subject_length_counts_characters() {
+        let mut draft = draft();
+        draft.subject = "靴".repeat(limits::PRODUCT_SUBJECT_MAX_CHARS);
+        assert!(draft.validate(&schema()).is_empty());
+
+        draft.subject.push('靴');
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::SubjectTooLong {
+                length: limits::PRODUCT_SUBJECT_MAX_CHARS + 1,
+                max: limits::PRODUCT_SUBJECT_MAX_CHARS,
+            }]
+        );
+    }
+
+    #[test]
+    fn image_count_is_bounded() {
+        let mut draft = draft();
+        draft.images.clear();
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::MissingImage]
+        );
+
+        let draft = (0..=limits::PRODUCT_IMAGES_MAX).fold(draft, |draft, index| {
+            draft.image(format!("https://sc04.alicdn.com/kf/H{index}.jpg"))
+        });
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::TooManyImages {
+                count: limits::PRODUCT_IMAGES_MAX + 1,
+                max: limits::PRODUCT_IMAGES_MAX,
+            }]
+        );
+    }
+
+    #[test]
+    fn keyword_count_is_bounded() {
+        let draft = (0..limits::PRODUCT_KEYWORDS_MAX).fold(draft(), |draft, index| {
+            draft.keyword(format!("boots {index}"))
+        });
+        assert!(draft.validate(&schema()).is_empty());
+
+        let draft = draft.keyword("one too many");
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::TooManyKeywords {
+                count: limits::PRODUCT_KEYWORDS_MAX + 1,
+                max: limits::PRODUCT_KEYWORDS_MAX,
+            }]
+        );
+    }
+
+    #[test]
+    fn skus_without_detail_need_a_min_price() {
+        let mut draft = draft();
+        draft.sku_details.remove("100:c:Color 1");
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::MissingPrice {
+                sku_key: Some("100:c:Color 1".to_string()),
+            }]
+        );
+
+        let draft = draft.trade_info(ProductTradeInfo {
+            min_order_quantity: None,
+            unit_type: None,
+            currency: None,
+            min_price: Some(39.9),
+            max_price: None,
+            ladder_prices: None,
+        });
+        assert!(draft.validate(&schema()).is_empty());
+    }
+
+    #[test]
+    fn details_must_match_a_generated_sku() {
+        let draft = draft().sku_detail("100:c:Color 2", detail(69.9));
+        assert_eq!(
+            draft.validate(&schema()),
+            vec![DraftViolation::UnknownSku {
+                sku_key: "100:c:Color 2".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn every_violation_is_collected() {
+        let mut draft = draft();
+        draft.subject.clear();
+        draft.images.clear();
+        draft.sku_details.clear();
+
+        let violations = draft.validate(&schema());
+        assert_eq!(violations.len(), 4);
+        assert_eq!(violations[0], DraftViolation::EmptySubject);
+        assert_eq!(violations[1], DraftViolation::MissingImage);
+        assert!(violations[2..].iter().all(|violation| matches!(
+            violation,
+            DraftViolation::MissingPrice { sku_key: Some(_) }
+        )));
+    }
+}
@@ -1,11 +1,12 @@
 use log::{info, warn};
 
 use crate::{
-    constants::{methods, urls},
-    model, IopClient,
+    constants::{limits, methods, urls},
+    model, IopClient, SignedParams,
 };
 use serde::{Deserialize, Deserializer, Serialize};
 
+use futures::stream::{self, StreamExt};
 use std::{collections::HashMap, vec};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -26,11 +27,11 @@ struct ProductGroup {
     group_name: Option<String>,
 
     #[serde(deserialize_with = "empty_object_as_none")]
-    children_id_list: Option<ChildrenIdList>,
+    children_id_list: Option<model::NumberList<i32>>,
     parent_id: Option<i32>,
 
     #[serde(deserialize_with = "empty_object_as_none")]
-    children_group: Option<ChildrenGroupList>,
+    children_group: Option<model::JavaList<ChildrenGroup>>,
     parent_id2: Option<i32>,
 }
 
@@ -59,14 +60,37 @@ where
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct ChildrenIdList {
-    number: Vec<i32>,
+struct ProductGroupAddResponse {
+    alibaba_icbu_product_group_add_response: ProductGroupAddResult,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct ChildrenGroupList {
-    #[serde(rename = "java.util._list")]
-    java_util_list: Vec<ChildrenGroup>,
+struct ProductGroupAddResult {
+    product_group: ProductGroupAdded,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductGroupAdded {
+    group_id: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductGroupUpdateResponse {
+    alibaba_icbu_product_group_update_response: ProductGroupWriteResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductGroupDeleteResponse {
+    alibaba_icbu_product_group_delete_response: ProductGroupWriteResult,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct ProductGroupWriteResult {
+    result: bool,
+    request_id: Option<String>,
+    _trace_id_: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -101,22 +125,7 @@ impl IopClient {
         &self,
         id: i32,
     ) -> Result<Vec<model::ProductGroup>, Box<dyn std::error::Error>> {
-        let mut map = HashMap::new();
-        map.insert("group_id".to_string(), id.to_string());
-        map.insert(
-            "method".to_string(),
-            methods::ALIBABA_ICBU_PRODUCT_GROUP_GET.to_string(),
-        );
-
-        let params = self.build_request_params(map).await;
-        let hash = self.generate_sign(None, params.clone());
-        let url = self.generate_url(urls::BASE_SYNC_URL.to_string(), params.clone(), hash);
-        info!("--------get_product_groups-------- url: {:#?}", url);
-
-        let response = self.client.get(&url).send().await?;
-        let result = response.json::<ProductGroupResponse>().await?;
-
-        let product_group = result.alibaba_icbu_product_group_get_response.product_group;
+        let product_group = self.fetch_product_group(id).await?;
         let children_group = match product_group.children_group {
             Some(children_group) => children_group,
             None => return Ok(vec![]),
@@ -139,10 +148,580 @@ impl IopClient {
             reply.push(model::ProductGroup {
                 group_id,
                 group_name: parent.group_name,
+                parent_id: Some(id),
                 children: None,
             });
         }
 
         Ok(reply)
     }
+
+    /// Retrieves a product group and, recursively, all groups below it.
+    ///
+    /// Each level of the tree is fetched with `alibaba.icbu.product.group.get`,
+    /// keeping at most `concurrency` requests in flight.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The identifier of the group to start from.
+    /// * `concurrency` - The maximum number of concurrent requests.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the root `ProductGroup` with `children` filled in at every
+    /// level (`Some(vec![])` for groups without children) if successful, or an error if
+    /// any request fails.
+    pub async fn get_product_group_tree(
+        &self,
+        root: i32,
+        concurrency: usize,
+    ) -> Result<model::ProductGroup, Box<dyn std::error::Error>> {
+        let root_group = self.fetch_product_group(root).await?;
+
+        let mut groups = HashMap::new();
+        let mut level = child_ids(&root_group);
+        groups.insert(root, root_group);
+
+        while !level.is_empty() {
+            let results: Vec<_> = stream::iter(level)
+                .map(|id| async move {
+                    let result = self
+                        .fetch_product_group(id)
+                        .await
+                        .map_err(|err| err.to_string());
+                    (id, result)
+                })
+                .buffer_unordered(concurrency.max(1))
+                .collect()
+                .await;
+
+            level = Vec::new();
+            for (id, result) in results {
+                let group = result?;
+                level.extend(
+                    child_ids(&group)
+                        .into_iter()
+                        .filter(|id| !groups.contains_key(id)),
+                );
+                groups.insert(id, group);
+            }
+        }
+
+        info!(
+            "--------get_product_group_tree-------- root: {}, groups: {}",
+            root,
+            groups.len()
+        );
+
+        Ok(assemble(root, None, &mut groups))
+    }
+
+    /// 新增分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.group.add&methodType=GET/POST)
+    ///
+    /// Creates a product group under `parent_id`.
+    ///
+    /// The name and the resulting depth are checked locally against ICBU's limits
+    /// before the request is sent.
+    ///
+    /// # Arguments
+    ///
+    /// * `parent_id` - The identifier of the parent group, `-1` for a top-level group.
+    /// * `group_name` - The name of the new group.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the new `ProductGroup` if successful, or an error if the
+    /// name or depth is invalid or the request fails.
+    pub async fn add_product_group(
+        &self,
+        parent_id: i32,
+        group_name: String,
+    ) -> Result<model::ProductGroup, Box<dyn std::error::Error>> {
+        let group_name = validate_group_name(
+            "Product group",
+            &group_name,
+            limits::PRODUCT_GROUP_NAME_MAX_CHARS,
+        )?
+        .to_string();
+        check_nesting(self.product_group_depth(parent_id).await?, 1)?;
+
+        let mut params = self.build_signed_params().await;
+        params.insert("group_name", group_name.as_str());
+        params.insert("parent_id", parent_id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_GROUP_ADD);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------add_product_group-------- url: {:#?}", url);
+
+        let response = self.client.post(&url).send().await?;
+        let result = response.json::<ProductGroupAddResponse>().await?;
+
+        Ok(model::ProductGroup {
+            group_id: result
+                .alibaba_icbu_product_group_add_response
+                .product_group
+                .group_id,
+            group_name,
+            parent_id: Some(parent_id),
+            children: None,
+        })
+    }
+
+    /// 修改分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.group.update&methodType=GET/POST)
+    ///
+    /// Renames a product group.
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - The identifier of the group.
+    /// * `group_name` - The new name.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the updated `ProductGroup` if successful, or an error if the
+    /// name is invalid or the request fails.
+    pub async fn rename_product_group(
+        &self,
+        group_id: i32,
+        group_name: String,
+    ) -> Result<model::ProductGroup, Box<dyn std::error::Error>> {
+        let group_name = validate_group_name(
+            "Product group",
+            &group_name,
+            limits::PRODUCT_GROUP_NAME_MAX_CHARS,
+        )?;
+
+        let mut params = self.build_signed_params().await;
+        params.insert("group_id", group_id.to_string());
+        params.insert("group_name", group_name);
+        self.update_product_group(params).await?;
+
+        self.product_group_model(group_id).await
+    }
+
+    /// 修改分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.group.update&methodType=GET/POST)
+    ///
+    /// Moves a product group, with the groups below it, under another parent.
+    ///
+    /// The subtree is walked first, so moving a group below itself, or deeper than
+    /// ICBU allows once the subtree's own levels are counted, is refused locally.
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - The identifier of the group.
+    /// * `new_parent_id` - The identifier of the new parent group, `-1` for the top level.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the moved `ProductGroup` if successful, or an error if the
+    /// move would create a cycle or exceed the depth limit, or the request fails.
+    pub async fn move_product_group(
+        &self,
+        group_id: i32,
+        new_parent_id: i32,
+    ) -> Result<model::ProductGroup, Box<dyn std::error::Error>> {
+        let mut children = HashMap::new();
+        let mut level = vec![group_id];
+        for _ in 0..=limits::PRODUCT_GROUP_MAX_DEPTH {
+            if level.is_empty() || level.contains(&new_parent_id) {
+                break;
+            }
+
+            let mut next = Vec::new();
+            for id in level {
+                let ids = child_ids(&self.fetch_product_group(id).await?);
+                next.extend(ids.iter().copied());
+                children.insert(id, ids);
+            }
+            level = next;
+        }
+
+        let height = subtree_height(group_id, new_parent_id, &children)?;
+        check_nesting(self.product_group_depth(new_parent_id).await?, height)?;
+
+        let mut params = self.build_signed_params().await;
+        params.insert("group_id", group_id.to_string());
+        params.insert("parent_id", new_parent_id.to_string());
+        self.update_product_group(params).await?;
+
+        self.product_group_model(group_id).await
+    }
+
+    /// 删除分组
+    ///
+    /// [官方文档](https://open.alibaba.com/doc/api.htm#/api?cid=20966&path=alibaba.icbu.product.group.delete&methodType=GET/POST)
+    ///
+    /// Deletes a product group.
+    ///
+    /// # Arguments
+    ///
+    /// * `group_id` - The identifier of the group.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` that is `Ok` if the group was deleted, or an error if the gateway
+    /// refused or the request fails.
+    pub async fn delete_product_group(
+        &self,
+        group_id: i32,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("group_id", group_id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_GROUP_DELETE);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------delete_product_group-------- url: {:#?}", url);
+
+        let response = self.client.post(&url).send().await?;
+        let result = response.json::<ProductGroupDeleteResponse>().await?;
+        if !result.alibaba_icbu_product_group_delete_response.result {
+            return Err(format!("Failed to delete product group {group_id}").into());
+        }
+
+        Ok(())
+    }
+
+    async fn update_product_group(
+        &self,
+        mut params: SignedParams<'_>,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_GROUP_UPDATE);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------update_product_group-------- url: {:#?}", url);
+
+        let response = self.client.post(&url).send().await?;
+        let result = response.json::<ProductGroupUpdateResponse>().await?;
+        if !result.alibaba_icbu_product_group_update_response.result {
+            return Err("Failed to update product group".into());
+        }
+
+        Ok(())
+    }
+
+    /// Returns the level of a group, `0` for the root and `1` for top-level groups.
+    pub(crate) async fn product_group_depth(
+        &self,
+        group_id: i32,
+    ) -> Result<usize, Box<dyn std::error::Error>> {
+        let mut depth = 0;
+        let mut current = group_id;
+        while current > 0 {
+            depth += 1;
+            if depth > limits::PRODUCT_GROUP_MAX_DEPTH {
+                break;
+            }
+            current = self
+                .fetch_product_group(current)
+                .await?
+                .parent_id
+                .unwrap_or(-1);
+        }
+        Ok(depth)
+    }
+
+    /// Fetches a group and converts it to the public model, children not expanded.
+    async fn product_group_model(
+        &self,
+        group_id: i32,
+    ) -> Result<model::ProductGroup, Box<dyn std::error::Error>> {
+        let group = self.fetch_product_group(group_id).await?;
+        Ok(model::ProductGroup {
+            group_id,
+            group_name: group.group_name.unwrap_or_default(),
+            parent_id: group.parent_id,
+            children: None,
+        })
+    }
+
+    /// Fetches a single product group as returned by the API.
+    async fn fetch_product_group(
+        &self,
+        id: i32,
+    ) -> Result<ProductGroup, Box<dyn std::error::Error>> {
+        let mut params = self.build_signed_params().await;
+        params.insert("group_id", id.to_string());
+        params.insert("method", methods::ALIBABA_ICBU_PRODUCT_GROUP_GET);
+
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
+        info!("--------fetch_product_group-------- url: {:#?}", url);
+
+        let body = self.send_deduplicated(&params, url).await?;
+        let result = serde_json::from_slice::<ProductGroupResponse>(&body)?;
+
+        Ok(result.alibaba_icbu_product_group_get_response.product_group)
+    }
+}
+
+/// Lists the children of a group, from `children_id_list` or else `children_group`.
+fn child_ids(group: &ProductGroup) -> Vec<i32> {
+    if let Some(children_id_list) = &group.children_id_list {
+        if !children_id_list.number.is_empty() {
+            return children_id_list.number.clone();
+        }
+    }
+
+    let children_group = match &group.children_group {
+        Some(children_group) => children_group,
+        None => return vec![],
+    };
+
+    let mut ids = Vec::new();
+    for child in &children_group.java_util_list {
+        match child.group_id.parse::<i32>() {
+            Ok(id) => ids.push(id),
+            Err(_) => warn!("Invalid group_id: {:?}", child.group_id),
+        }
+    }
+    ids
+}
+
+/// Builds the `model::ProductGroup` tree below `id` from the fetched groups.
+fn assemble(
+    id: i32,
+    parent_id: Option<i32>,
+    groups: &mut HashMap<i32, ProductGroup>,
+) -> model::ProductGroup {
+    let group = groups.remove(&id);
+    let children = group.as_ref().map(child_ids).unwrap_or_default();
+    let (group_name, own_parent_id) = match group {
+        Some(group) => (group.group_name.unwrap_or_default(), group.parent_id),
+        None => (String::new(), None),
+    };
+
+    model::ProductGroup {
+        group_id: id,
+        group_name,
+        parent_id: parent_id.or(own_parent_id),
+        children: Some(
+            children
+                .into_iter()
+                .map(|child| assemble(child, Some(id), groups))
+                .collect(),
+        ),
+    }
+}
+
+/// Counts the levels of the subtree below `group_id`, itself included, from the
+/// children fetched so far, refusing a move below `new_parent_id` that would put
+/// the group below itself.
+///
+/// Counting stops once the subtree is deeper than any group may be nested.
+fn subtree_height(
+    group_id: i32,
+    new_parent_id: i32,
+    children: &HashMap<i32, Vec<i32>>,
+) -> Result<usize, Box<dyn std::error::Error>> {
+    let mut height = 0;
+    let mut level = vec![group_id];
+    while !level.is_empty() && height <= limits::PRODUCT_GROUP_MAX_DEPTH {
+        if level.contains(&new_parent_id) {
+            return Err(format!("Product group {group_id} cannot be moved below itself").into());
+        }
+        height += 1;
+
+        level = level
+            .iter()
+            .flat_map(|id| children.get(id).into_iter().flatten().copied())
+            .collect();
+    }
+    Ok(height)
+}
+
+/// Checks that `height` levels of groups fit below a parent at `parent_depth`.
+fn check_nesting(parent_depth: usize, height: usize) -> Result<(), Box<dyn std::error::Error>> {
+    if parent_depth + height > limits::PRODUCT_GROUP_MAX_DEPTH {
+        return Err(format!(
+            "Product groups cannot be nested deeper than {} levels",
+            limits::PRODUCT_GROUP_MAX_DEPTH
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Checks a group name against the length limit of its kind of group.
+///
+/// # Arguments
+///
+/// * `kind` - The kind of group, e.g. `Product group`, used in the error message.
+/// * `group_name` - The name to check.
+/// * `max_chars` - The maximum number of characters.
+///
+/// # Returns
+///
+/// The name without surrounding whitespace, as it should be sent, or an error if it
+/// is empty or too long.
+pub(crate) fn validate_group_name<'a>(
+    kind: &str,
+    group_name: &'a str,
+    max_chars: usize,
+) -> Result<&'a str, Box<dyn std::error::Error>> {
+    let group_name = group_name.trim();
+    let length = group_name.chars().count();
+    if length == 0 {
+        return Err(format!("{kind} name cannot be empty").into());
+    }
+    if length > max_chars {
+        return Err(format!("{kind} name cannot exceed {max_chars} characters").into());
+    }
+    Ok(group_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn group(group_id: i32, parent_id: i32, children_id_list: &[i32]) -> ProductGroup {
+        serde_json::from_value(json!({
+            "group_id": group_id,
+            "group_name": format!("Group {}", group_id),
+            "children_id_list": if children_id_list.is_empty() {
+                json!({})
+            } else {
+                json!({ "number": children_id_list })
+            },
+            "parent_id": parent_id,
+            "children_group": {},
+            "parent_id2": null,
+        }))
+        .unwrap()
+    }
+
+    /// 1 -> {2, 3}, 2 -> {4}.
+    fn subtree() -> HashMap<i32, Vec<i32>> {
+        HashMap::from([(1, vec![2, 3]), (2, vec![4]), (3, vec![]), (4, vec![])])
+    }
+
+    #[test]
+    fn subtree_height_counts_levels() {
+        assert_eq!(subtree_height(1, 10, &subtree()).unwrap(), 3);
+        assert_eq!(subtree_height(2, 10, &subtree()).unwrap(), 2);
+        assert_eq!(subtree_height(3, 10, &subtree()).unwrap(), 1);
+    }
+
+    #[test]
+    fn moving_below_itself_is_refused() {
+        for new_parent_id in [1, 2, 4] {
+            let err = subtree_height(1, new_parent_id, &subtree()).unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                "Product group 1 cannot be moved below itself"
+            );
+        }
+        assert!(subtree_height(2, 3, &subtree()).is_ok());
+    }
+
+    #[test]
+    fn subtree_height_stops_on_cyclic_data() {
+        let children = HashMap::from([(1, vec![2]), (2, vec![1])]);
+        assert_eq!(
+            subtree_height(1, 10, &children).unwrap(),
+            limits::PRODUCT_GROUP_MAX_DEPTH + 1
+        );
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        assert!(check_nesting(0, 3).is_ok());
+        assert!(check_nesting(1, 2).is_ok());
+        assert!(check_nesting(2, 2).is_err());
+        assert!(check_nesting(3, 1).is_err());
+        let err = check_nesting(0, 4).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Product groups cannot be nested deeper than 3 levels"
+        );
+    }
+
+    #[test]
+    fn child_ids_fall_back_to_children_group() {
+        let listed: ProductGroup = serde_json::from_value(json!({
+            "group_id": 1,
+            "group_name": "Group 1",
+            "children_id_list": {},
+            "parent_id": -1,
+            "children_group": { "java.util._list": [
+                { "group_id": "2", "group_name": "Group 2" },
+                { "group_id": "x", "group_name": "Broken" },
+                { "group_id": "3", "group_name": "Group 3" },
+            ] },
+            "parent_id2": null,
+        }))
+        .unwrap();
+        assert_eq!(child_ids(&listed), vec![2, 3]);
+        assert_eq!(child_ids(&group(1, -1, &[5, 6])), vec![5, 6]);
+    }
+
+    #[test]
+    fn tree_is_assembled_from_fetched_groups() {
+        let mut groups = HashMap::from([
+            (1, group(1, -1, &[2, 3])),
+            (2, group(2, 1, &[4])),
+            (3, group(3, 1, &[])),
+            (4, group(4, 2, &[])),
+        ]);
+
+        let root = assemble(1, None, &mut groups);
+        assert!(groups.is_empty());
+        assert_eq!(root.group_id, 1);
+        assert_eq!(root.parent_id, Some(-1));
+
+        let children = root.children.unwrap();
+        assert_eq!(
+            children
+                .iter()
+                .map(|child| child.group_id)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+        assert_eq!(children[0].parent_id, Some(1));
+        assert_eq!(children[1].children.as_deref().map(<[_]>::len), Some(0));
+
+        let grandchild = &children[0].children.as_ref().unwrap()[0];
+        assert_eq!(grandchild.group_id, 4);
+        assert_eq!(grandchild.group_name, "Group 4");
+        assert_eq!(grandchild.parent_id, Some(2));
+    }
+
+    #[test]
+    fn missing_groups_are_assembled_empty() {
+        let mut groups = HashMap::from([(1, group(1, -1, &[2]))]);
+        let root = assemble(1, None, &mut groups);
+
+        let missing = &root.children.unwrap()[0];
+        assert_eq!(missing.group_id, 2);
+        assert_eq!(missing.group_name, "");
+        assert_eq!(missing.parent_id, Some(1));
+        assert_eq!(missing.children.as_deref().map(<[_]>::len), Some(0));
+    }
+
+    #[test]
+    fn group_names_are_trimmed() {
+        assert_eq!(
+            validate_group_name("Product group", "  Shoes\n", 30).unwrap(),
+            "Shoes"
+        );
+        assert_eq!(
+            validate_group_name("Product group", "   ", 30)
+                .unwrap_err()
+                .to_string(),
+            "Product group name cannot be empty"
+        );
+        // Surrounding whitespace does not count towards the limit.
+        assert!(validate_group_name("Photo bank group", " abc ", 3).is_ok());
+        assert_eq!(
+            validate_group_name("Photo bank group", "abcd", 3)
+                .unwrap_err()
+                .to_string(),
+            "Photo bank group name cannot exceed 3 characters"
+        );
+    }
 }
@@ -0,0 +1,799 @@
+use std::{collections::HashSet, fmt, io::Read};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{constants::limits, model, product_group::validate_group_name, IopClient};
+
+/// A product group of the desired hierarchy.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct DesiredProductGroup {
+    /// The existing group this entry stands for. Only needed to rename a group in
+    /// place; groups without an ID are matched to existing ones by name.
+    #[serde(default)]
+    pub group_id: Option<i32>,
+    pub group_name: String,
+
+    #[serde(default)]
+    pub children: Vec<DesiredProductGroup>,
+}
+
+/// The desired product group hierarchy, as kept in a YAML or JSON file.
+///
+/// ```yaml
+/// groups:
+///   - group_name: Shoes
+///     children:
+///       - group_name: Boots
+///   - group_id: 123
+///     group_name: Bags
+/// ```
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct DesiredProductGroups {
+    #[serde(default)]
+    pub groups: Vec<DesiredProductGroup>,
+}
+
+impl DesiredProductGroups {
+    /// Reads the desired hierarchy from a JSON document.
+    pub fn from_json<R: Read>(reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Reads the desired hierarchy from a YAML document.
+    pub fn from_yaml<R: Read>(reader: R) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(serde_yaml::from_reader(reader)?)
+    }
+}
+
+/// The parent of a group to create.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupParent {
+    /// A group that already exists.
+    Existing(i32),
+    /// A group created earlier in the same plan, by index into `operations`.
+    Planned(usize),
+}
+
+/// A single change to the product group hierarchy.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ProductGroupOperation {
+    Create {
+        parent: GroupParent,
+        group_name: String,
+    },
+    Rename {
+        group_id: i32,
+        from: String,
+        to: String,
+    },
+    Delete {
+        group_id: i32,
+        group_name: String,
+    },
+}
+
+impl fmt::Display for ProductGroupOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProductGroupOperation::Create { parent, group_name } => match parent {
+                GroupParent::Existing(parent_id) => {
+                    write!(f, "Create {group_name:?} under group {parent_id}")
+                }
+                GroupParent::Planned(index) => {
+                    write!(f, "Create {group_name:?} under operation #{index}")
+                }
+            },
+            ProductGroupOperation::Rename { group_id, from, to } => {
+                write!(f, "Rename group {group_id} from {from:?} to {to:?}")
+            }
+            ProductGroupOperation::Delete {
+                group_id,
+                group_name,
+            } => write!(f, "Delete group {group_id} ({group_name:?})"),
+        }
+    }
+}
+
+/// The ordered operations turning the current product groups into the desired ones.
+///
+/// Deletions come first, bottom-up, so the names they free can be reused. Renames
+/// follow; groups trading names go through a temporary name. Creations come last,
+/// top-down.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ProductGroupPlan {
+    pub root: i32,
+    pub operations: Vec<ProductGroupOperation>,
+}
+
+impl ProductGroupPlan {
+    /// Returns `true` if the current groups already match the desired ones.
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+}
+
+/// An error that stopped `apply_product_group_plan`.
+///
+/// Operations before `index` were applied; the rest were not attempted. Planning
+/// again from the live tree picks up where the run stopped.
+#[derive(Debug)]
+pub struct ProductGroupApplyError {
+    pub index: usize,
+    pub operation: ProductGroupOperation,
+    pub message: String,
+}
+
+impl fmt::Display for ProductGroupApplyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Operation #{} ({}) failed: {}",
+            self.index, self.operation, self.message
+        )
+    }
+}
+
+impl std::error::Error for ProductGroupApplyError {}
+
+impl IopClient {
+    /// Plans the changes turning the product groups below `root` into `desired`.
+    ///
+    /// The current tree is read with `get_product_group_tree`. Desired groups are
+    /// matched to existing siblings by `group_id` if set, by name otherwise; existing
+    /// groups left unmatched are deleted together with their children.
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The identifier of the group the hierarchy lives under, `-1` for the top level.
+    /// * `desired` - The desired hierarchy below `root`.
+    /// * `concurrency` - The maximum number of concurrent requests while reading the tree.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the `ProductGroupPlan` if successful, or an error if the
+    /// desired hierarchy is invalid or a request fails.
+    pub async fn plan_product_groups(
+        &self,
+        root: i32,
+        desired: &DesiredProductGroups,
+        concurrency: usize,
+    ) -> Result<ProductGroupPlan, Box<dyn std::error::Error>> {
+        let current = self.get_product_group_tree(root, concurrency).await?;
+        let depth = self.product_group_depth(root).await?;
+
+        let mut planner = Planner::default();
+        planner.plan_level(
+            GroupParent::Existing(root),
+            current.children.as_deref().unwrap_or(&[]),
+            &desired.groups,
+            depth + 1,
+        )?;
+
+        Ok(ProductGroupPlan {
+            root,
+            operations: planner.into_operations(),
+        })
+    }
+
+    /// Applies a plan in order, stopping at the first failure.
+    ///
+    /// # Arguments
+    ///
+    /// * `plan` - The plan returned by `plan_product_groups`.
+    /// * `progress` - Called after each applied operation with its index, the number
+    ///   of operations and the operation.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the created groups if every operation succeeded, or a
+    /// `ProductGroupApplyError` naming the operation that failed.
+    pub async fn apply_product_group_plan<F>(
+        &self,
+        plan: &ProductGroupPlan,
+        mut progress: F,
+    ) -> Result<Vec<model::ProductGroup>, ProductGroupApplyError>
+    where
+        F: FnMut(usize, usize, &ProductGroupOperation),
+    {
+        let total = plan.operations.len();
+        let mut created_ids: Vec<Option<i32>> = vec![None; total];
+        let mut created = Vec::new();
+
+        for (index, operation) in plan.operations.iter().enumerate() {
+            info!("--------apply_product_group_plan-------- {}", operation);
+
+            let fail = |message: String| ProductGroupApplyError {
+                index,
+                operation: operation.clone(),
+                message,
+            };
+
+            match operation {
+                ProductGroupOperation::Create { parent, group_name } => {
+                    let parent_id = match parent {
+                        GroupParent::Existing(parent_id) => *parent_id,
+                        GroupParent::Planned(planned) => {
+                            match created_ids.get(*planned).copied().flatten() {
+                                Some(parent_id) => parent_id,
+                                None => {
+                                    return Err(fail(format!(
+                                        "Operation #{planned} did not create a group"
+                                    )))
+                                }
+                            }
+                        }
+                    };
+
+                    let result = self.add_product_group(parent_id, group_name.clone()).await;
+                    let group = result.map_err(|err| fail(err.to_string()))?;
+                    created_ids[index] = Some(group.group_id);
+                    created.push(group);
+                }
+                ProductGroupOperation::Rename { group_id, to, .. } => {
+                    let result = self.rename_product_group(*group_id, to.clone()).await;
+                    result.map_err(|err| fail(err.to_string()))?;
+                }
+                ProductGroupOperation::Delete { group_id, .. } => {
+                    let result = self.delete_product_group(*group_id).await;
+                    result.map_err(|err| fail(err.to_string()))?;
+                }
+            }
+
+            progress(index, total, operation);
+        }
+
+        Ok(created)
+    }
+}
+
+#[derive(Default)]
+struct Planner {
+    deletes: Vec<ProductGroupOperation>,
+    /// Renames to a temporary name, clearing names other siblings are renamed to.
+    temporary_renames: Vec<ProductGroupOperation>,
+    renames: Vec<ProductGroupOperation>,
+    /// Renames from a temporary name, once the final names are free.
+    final_renames: Vec<ProductGroupOperation>,
+    creates: Vec<ProductGroupOperation>,
+}
+
+impl Planner {
+    fn plan_level(
+        &mut self,
+        parent: GroupParent,
+        current: &[model::ProductGroup],
+        desired: &[DesiredProductGroup],
+        level: usize,
+    ) -> Result<(), String> {
+        let mut names = HashSet::new();
+        let mut matched = HashSet::new();
+        let mut existing: Vec<Option<&model::ProductGroup>> = vec![None; desired.len()];
+
+        // Explicit IDs are matched first, so a group matched by name cannot take an
+        // existing group that a later entry claims by ID.
+        for (index, group) in desired.iter().enumerate() {
+            let group_name = validate_group_name(
+                "Product group",
+                &group.group_name,
+                limits::PRODUCT_GROUP_NAME_MAX_CHARS,
+            )
+            .map_err(|err| err.to_string())?;
+            if level > limits::PRODUCT_GROUP_MAX_DEPTH {
+                return Err(format!(
+                    "Product group {:?} would be nested deeper than {} levels",
+                    group.group_name,
+                    limits::PRODUCT_GROUP_MAX_DEPTH
+                ));
+            }
+            if !names.insert(group_name) {
+                return Err(format!(
+                    "Product group {:?} appears twice under the same parent",
+                    group_name
+                ));
+            }
+
+            if let Some(group_id) = group.group_id {
+                let found = match current.iter().find(|c| c.group_id == group_id) {
+                    Some(found) => found,
+                    None => {
+                        return Err(format!(
+                            "Product group {group_id} does not exist under its desired parent"
+                        ))
+                    }
+                };
+                if !matched.insert(group_id) {
+                    return Err(format!("Product group {group_id} is matched twice"));
+                }
+                existing[index] = Some(found);
+            }
+        }
+
+        for (index, group) in desired.iter().enumerate() {
+            if group.group_id.is_none() {
+                existing[index] = current.iter().find(|c| {
+                    c.group_name == group.group_name.trim() && !matched.contains(&c.group_id)
+                });
+                if let Some(found) = existing[index] {
+                    matched.insert(found.group_id);
+                }
+            }
+        }
+
+        let mut renames = Vec::new();
+        for (group, existing) in desired.iter().zip(existing) {
+            match existing {
+                Some(existing) => {
+                    if existing.group_name != group.group_name.trim() {
+                        renames.push((existing, group.group_name.trim().to_string()));
+                    }
+                    self.plan_level(
+                        GroupParent::Existing(existing.group_id),
+                        existing.children.as_deref().unwrap_or(&[]),
+                        &group.children,
+                        level + 1,
+                    )?;
+                }
+                None => {
+                    self.creates.push(ProductGroupOperation::Create {
+                        parent,
+                        group_name: group.group_name.trim().to_string(),
+                    });
+                    let planned = GroupParent::Planned(self.creates.len() - 1);
+                    self.plan_level(planned, &[], &group.children, level + 1)?;
+                }
+            }
+        }
+
+        for group in current {
+            if !matched.contains(&group.group_id) {
+                self.delete_subtree(group);
+            }
+        }
+
+        // Unmatched siblings are deleted first, so a name is only still taken if the
+        // sibling holding it is kept, and then renamed away.
+        for (existing, to) in renames {
+            let taken = current
+                .iter()
+                .any(|c| matched.contains(&c.group_id) && c.group_name == to);
+            if !taken {
+                self.renames.push(ProductGroupOperation::Rename {
+                    group_id: existing.group_id,
+                    from: existing.group_name.clone(),
+                    to,
+                });
+                continue;
+            }
+
+            // The temporary name must itself be free and acceptable to the gateway.
+            let mut temporary = format!("~{}", existing.group_id);
+            while names.contains(temporary.as_str())
+                || current.iter().any(|c| c.group_name == temporary)
+            {
+                temporary.insert(0, '~');
+            }
+            validate_group_name(
+                "Product group",
+                &temporary,
+                limits::PRODUCT_GROUP_NAME_MAX_CHARS,
+            )
+            .map_err(|err| err.to_string())?;
+
+            self.temporary_renames.push(ProductGroupOperation::Rename {
+                group_id: existing.group_id,
+                from: existing.group_name.clone(),
+                to: temporary.clone(),
+            });
+            self.final_renames.push(ProductGroupOperation::Rename {
+                group_id: existing.group_id,
+                from: temporary,
+                to,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn delete_subtree(&mut self, group: &model::ProductGroup) {
+        for child in group.children.iter().flatten() {
+            self.delete_subtree(child);
+        }
+        self.deletes.push(ProductGroupOperation::Delete {
+            group_id: group.group_id,
+            group_name: group.group_name.clone(),
+        });
+    }
+
+    /// Orders the operations, pointing planned parents at their final index.
+    fn into_operations(self) -> Vec<ProductGroupOperation> {
+        let mut operations = self.deletes;
+        operations.extend(self.temporary_renames);
+        operations.extend(self.renames);
+        operations.extend(self.final_renames);
+
+        let offset = operations.len();
+        operations.extend(self.creates.into_iter().map(|operation| match operation {
+            ProductGroupOperation::Create {
+                parent: GroupParent::Planned(index),
+                group_name,
+            } => ProductGroupOperation::Create {
+                parent: GroupParent::Planned(index + offset),
+                group_name,
+            },
+            operation => operation,
+        }));
+        operations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    const ROOT: i32 = -1;
+
+    fn group(
+        group_id: i32,
+        group_name: &str,
+        children: Vec<model::ProductGroup>,
+    ) -> model::ProductGroup {
+        model::ProductGroup {
+            group_id,
+            group_name: group_name.to_string(),
+            parent_id: None,
+            children: Some(children),
+        }
+    }
+
+    fn desired(
+        group_id: Option<i32>,
+        group_name: &str,
+        children: Vec<DesiredProductGroup>,
+    ) -> DesiredProductGroup {
+        DesiredProductGroup {
+            group_id,
+            group_name: group_name.to_string(),
+            children,
+        }
+    }
+
+    fn plan(
+        current: &[model::ProductGroup],
+        desired: &[DesiredProductGroup],
+    ) -> Result<Vec<ProductGroupOperation>, String> {
+        let mut planner = Planner::default();
+        planner.plan_level(GroupParent::Existing(ROOT), current, desired, 1)?;
+        Ok(planner.into_operations())
+    }
+
+    /// Applies the operations to a copy of `current` the way the API would, failing
+    /// where two siblings would share a name, and returns the resulting
+    /// `(parent name, name)` pairs.
+    fn simulate(
+        current: &[model::ProductGroup],
+        operations: &[ProductGroupOperation],
+    ) -> Vec<(String, String)> {
+        fn flatten(
+            groups: &[model::ProductGroup],
+            parent: i32,
+            out: &mut HashMap<i32, (i32, String)>,
+        ) {
+            for group in groups {
+                out.insert(group.group_id, (parent, group.group_name.clone()));
+                flatten(
+                    group.children.as_deref().unwrap_or(&[]),
+                    group.group_id,
+                    out,
+                );
+            }
+        }
+
+        let mut groups = HashMap::new();
+        flatten(current, ROOT, &mut groups);
+        let mut created = HashMap::new();
+        let mut next_id = 1000;
+
+        for (index, operation) in operations.iter().enumerate() {
+            let (group_id, parent, name) = match operation {
+                ProductGroupOperation::Create { parent, group_name } => {
+                    let parent = match parent {
+                        GroupParent::Existing(parent_id) => *parent_id,
+                        GroupParent::Planned(planned) => created[planned],
+                    };
+                    next_id += 1;
+                    created.insert(index, next_id);
+                    (next_id, parent, group_name.clone())
+                }
+                ProductGroupOperation::Rename { group_id, from, to } => {
+                    let (parent, name) = groups[group_id].clone();
+                    assert_eq!(&name, from, "operation #{index} renames from a stale name");
+                    (*group_id, parent, to.clone())
+                }
+                ProductGroupOperation::Delete { group_id, .. } => {
+                    assert!(
+                        groups.values().all(|(parent, _)| parent != group_id),
+                        "operation #{index} deletes a group with children"
+                    );
+                    groups.remove(group_id);
+                    continue;
+                }
+            };
+
+            let collides = groups
+                .iter()
+                .any(|(id, (p, n))| *id != group_id && *p == parent && *n == name);
+            assert!(
+                !collides,
+                "operation #{index} ({operation}) collides with a sibling"
+            );
+            groups.insert(group_id, (parent, name));
+        }
+
+        let mut result: Vec<_> = groups
+            .values()
+            .map(|(parent, name)| {
+                let parent_name = groups
+                    .get(parent)
+                    .map(|(_, n)| n.clone())
+                    .unwrap_or_default();
+                (parent_name, name.clone())
+            })
+            .collect();
+        result.sort();
+        result
+    }
+
+    /// Lists `(parent name, name)` pairs, as returned by `simulate`.
+    fn names(tree: &[(&str, &str)]) -> Vec<(String, String)> {
+        let mut tree: Vec<_> = tree
+            .iter()
+            .map(|(parent, name)| (parent.to_string(), name.to_string()))
+            .collect();
+        tree.sort();
+        tree
+    }
+
+    #[test]
+    fn unchanged_tree_needs_no_operations() {
+        let current = [group(1, "Shoes", vec![group(2, "Boots", vec![])])];
+        let operations = plan(
+            &current,
+            &[desired(None, "Shoes", vec![desired(None, "Boots", vec![])])],
+        )
+        .unwrap();
+
+        assert!(operations.is_empty());
+    }
+
+    #[test]
+    fn rename_into_name_of_deleted_sibling() {
+        let current = [group(1, "A", vec![]), group(2, "B", vec![])];
+        let operations = plan(&current, &[desired(Some(2), "A", vec![])]).unwrap();
+
+        assert_eq!(
+            operations,
+            [
+                ProductGroupOperation::Delete {
+                    group_id: 1,
+                    group_name: "A".to_string(),
+                },
+                ProductGroupOperation::Rename {
+                    group_id: 2,
+                    from: "B".to_string(),
+                    to: "A".to_string(),
+                },
+            ]
+        );
+        assert_eq!(simulate(&current, &operations), names(&[("", "A")]));
+    }
+
+    #[test]
+    fn swapped_names_go_through_temporary_names() {
+        let current = [group(1, "A", vec![]), group(2, "B", vec![])];
+        let operations = plan(
+            &current,
+            &[desired(Some(1), "B", vec![]), desired(Some(2), "A", vec![])],
+        )
+        .unwrap();
+
+        assert_eq!(operations.len(), 4);
+        assert_eq!(
+            simulate(&current, &operations),
+            names(&[("", "A"), ("", "B")])
+        );
+    }
+
+    #[test]
+    fn rename_chain_frees_names_in_order() {
+        let current = [group(1, "A", vec![]), group(2, "B", vec![])];
+        let operations = plan(
+            &current,
+            &[desired(Some(1), "B", vec![]), desired(Some(2), "C", vec![])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            simulate(&current, &operations),
+            names(&[("", "B"), ("", "C")])
+        );
+    }
+
+    #[test]
+    fn creates_nest_under_planned_parents_after_deletes() {
+        let current = [group(1, "Old", vec![group(2, "Older", vec![])])];
+        let operations = plan(
+            &current,
+            &[desired(None, "Shoes", vec![desired(None, "Boots", vec![])])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            operations,
+            [
+                ProductGroupOperation::Delete {
+                    group_id: 2,
+                    group_name: "Older".to_string(),
+                },
+                ProductGroupOperation::Delete {
+                    group_id: 1,
+                    group_name: "Old".to_string(),
+                },
+                ProductGroupOperation::Create {
+                    parent: GroupParent::Existing(ROOT),
+                    group_name: "Shoes".to_string(),
+                },
+                ProductGroupOperation::Create {
+                    parent: GroupParent::Planned(2),
+                    group_name: "Boots".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            simulate(&current, &operations),
+            names(&[("", "Shoes"), ("Shoes", "Boots")])
+        );
+    }
+
+    #[test]
+    fn create_reuses_name_renamed_away() {
+        let current = [group(1, "A", vec![])];
+        let operations = plan(
+            &current,
+            &[desired(Some(1), "B", vec![]), desired(None, "A", vec![])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            simulate(&current, &operations),
+            names(&[("", "A"), ("", "B")])
+        );
+    }
+
+    #[test]
+    fn duplicate_sibling_names_are_rejected() {
+        let err = plan(
+            &[],
+            &[desired(None, "A", vec![]), desired(None, "A", vec![])],
+        )
+        .unwrap_err();
+
+        assert!(err.contains("appears twice"), "{err}");
+    }
+
+    #[test]
+    fn too_deep_hierarchy_is_rejected() {
+        let mut nested = desired(None, "Leaf", vec![]);
+        for level in 0..limits::PRODUCT_GROUP_MAX_DEPTH {
+            nested = desired(None, &format!("Level {level}"), vec![nested]);
+        }
+
+        let err = plan(&[], &[nested]).unwrap_err();
+        assert!(err.contains("deeper than"), "{err}");
+    }
+
+    #[test]
+    fn unknown_group_id_is_rejected() {
+        let err = plan(&[group(1, "A", vec![])], &[desired(Some(9), "A", vec![])]).unwrap_err();
+
+        assert!(err.contains("does not exist"), "{err}");
+    }
+
+    #[test]
+    fn explicit_ids_are_matched_before_names() {
+        let current = [group(1, "A", vec![])];
+        let operations = plan(
+            &current,
+            &[desired(None, "A", vec![]), desired(Some(1), "B", vec![])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            operations,
+            [
+                ProductGroupOperation::Rename {
+                    group_id: 1,
+                    from: "A".to_string(),
+                    to: "B".to_string(),
+                },
+                ProductGroupOperation::Create {
+                    parent: GroupParent::Existing(ROOT),
+                    group_name: "A".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            simulate(&current, &operations),
+            names(&[("", "A"), ("", "B")])
+        );
+    }
+
+    #[test]
+    fn same_id_claimed_twice_is_rejected() {
+        let err = plan(
+            &[group(1, "A", vec![])],
+            &[desired(Some(1), "A", vec![]), desired(Some(1), "B", vec![])],
+        )
+        .unwrap_err();
+
+        assert!(err.contains("matched twice"), "{err}");
+    }
+
+    #[test]
+    fn temporary_names_avoid_sibling_names() {
+        let current = [
+            group(1, "A", vec![]),
+            group(2, "B", vec![]),
+            group(3, "~1", vec![]),
+        ];
+        let operations = plan(
+            &current,
+            &[
+                desired(Some(1), "B", vec![]),
+                desired(Some(2), "A", vec![]),
+                desired(Some(3), "~1", vec![]),
+            ],
+        )
+        .unwrap();
+
+        assert!(operations.contains(&ProductGroupOperation::Rename {
+            group_id: 1,
+            from: "A".to_string(),
+            to: "~~1".to_string(),
+        }));
+        assert_eq!(
+            simulate(&current, &operations),
+            names(&[("", "A"), ("", "B"), ("", "~1")])
+        );
+    }
+
+    #[test]
+    fn names_are_compared_and_sent_trimmed() {
+        let current = [group(1, "Shoes", vec![])];
+        let operations = plan(
+            &current,
+            &[
+                desired(None, " Shoes ", vec![]),
+                desired(None, "Bags\t", vec![]),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(
+            operations,
+            [ProductGroupOperation::Create {
+                parent: GroupParent::Existing(ROOT),
+                group_name: "Bags".to_string(),
+            }]
+        );
+
+        let err = plan(
+            &[],
+            &[desired(None, "A", vec![]), desired(None, " A", vec![])],
+        )
+        .unwrap_err();
+        assert!(err.contains("appears twice"), "{err}");
+    }
+}
@@ -0,0 +1,431 @@
+use std::{borrow::Cow, fmt::Write};
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use urlencoding::encode;
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// The parameters `to_form` keeps in the query string.
+const SYSTEM_PARAMS: &[&str] = &[
+    "access_token",
+    "app_key",
+    "language",
+    "method",
+    "sign_method",
+    "simplify",
+    "timestamp",
+];
+
+/// HMAC-SHA256 state keyed with the app secret.
+///
+/// The key schedule is computed once; signing clones the keyed state instead of
+/// re-deriving it from the secret on every request.
+#[derive(Clone)]
+pub struct SigningKey {
+    mac: HmacSha256,
+}
+
+impl SigningKey {
+    /// Creates a signing key from the app secret.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_secret` - The secret key associated with the application ID.
+    pub fn new(app_secret: &str) -> Self {
+        SigningKey {
+            mac: HmacSha256::new_from_slice(app_secret.as_bytes())
+                .expect("HMAC can take key of any size"),
+        }
+    }
+
+    /// Returns a fresh HMAC context keyed with the app secret.
+    pub(crate) fn mac(&self) -> HmacSha256 {
+        self.mac.clone()
+    }
+}
+
+/// Request parameters kept sorted by name, ready to be signed.
+///
+/// Names and values are borrowed where the caller can lend them (constants, the
+/// app key) and owned otherwise. Because entries are always sorted, signing and
+/// URL serialization happen together in a single pass without re-sorting or
+/// cloning the parameters.
+#[derive(Clone, Debug, Default)]
+pub struct SignedParams<'a> {
+    entries: Vec<(Cow<'a, str>, Cow<'a, str>)>,
+}
+
+impl<'a> SignedParams<'a> {
+    /// Creates an empty parameter set.
+    pub fn new() -> Self {
+        SignedParams {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Creates an empty parameter set with room for `capacity` parameters.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SignedParams {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Inserts a parameter, replacing any previous value with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The parameter name.
+    /// * `value` - The parameter value, unencoded.
+    pub fn insert(&mut self, key: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>) {
+        let key = key.into();
+        let value = value.into();
+        match self
+            .entries
+            .binary_search_by(|(k, _)| k.as_ref().cmp(key.as_ref()))
+        {
+            Ok(index) => self.entries[index].1 = value,
+            Err(index) => self.entries.insert(index, (key, value)),
+        }
+    }
+
+    /// Returns the value of the parameter named `key`, if present.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .binary_search_by(|(k, _)| k.as_ref().cmp(key))
+            .ok()
+            .map(|index| self.entries[index].1.as_ref())
+    }
+
+    /// Returns the number of parameters.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if there are no parameters.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the parameters in name order.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_ref(), v.as_ref()))
+    }
+
+    /// Computes the request signature.
+    ///
+    /// 拼接参数名与参数值 [官方文档](https://open.alibaba.com/doc/doc.htm?spm=a2o9m.11193535.0.0.55fb2f04MHBYoD&docId=107343&docType=1#/?docId=134)
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The signing key derived from the app secret.
+    /// * `api_path` - The API path to prepend, required by the `/rest` endpoints.
+    ///
+    /// # Returns
+    ///
+    /// A `String` representing the computed HMAC-SHA256 signature in uppercase hexadecimal.
+    pub fn sign(&self, key: &SigningKey, api_path: Option<&str>) -> String {
+        let mut sign = String::with_capacity(64);
+        let digest = self.digest(key, api_path, |_| {});
+        push_hex(&mut sign, &digest);
+        sign
+    }
+
+    /// Builds the signed request URL, computing the signature while serializing
+    /// the query string.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The signing key derived from the app secret.
+    /// * `base_url` - The endpoint the query string is appended to.
+    /// * `api_path` - The API path to prepend to the signed string, required by the
+    ///   `/rest` endpoints.
+    ///
+    /// # Returns
+    ///
+    /// A `String` representing the URL with percent-encoded parameters and the `sign`
+    /// parameter last.
+    pub fn to_url(&self, key: &SigningKey, base_url: &str, api_path: Option<&str>) -> String {
+        self.serialize(key, base_url, api_path, |_| true).0
+    }
+
+    /// Builds a signed form POST, keeping large business parameters out of the URL.
+    ///
+    /// The system parameters (`app_key`, `access_token`, `method`, ...) and `sign` go
+    /// into the query string; every other parameter goes into the body. The signature
+    /// still covers all of them.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The signing key derived from the app secret.
+    /// * `base_url` - The endpoint the query string is appended to.
+    /// * `api_path` - The API path to prepend to the signed string, required by the
+    ///   `/rest` endpoints.
+    ///
+    /// # Returns
+    ///
+    /// The URL, and the `application/x-www-form-urlencoded` body.
+    pub fn to_form(
+        &self,
+        key: &SigningKey,
+        base_url: &str,
+        api_path: Option<&str>,
+    ) -> (String, String) {
+        self.serialize(key, base_url, api_path, |name| {
+            SYSTEM_PARAMS.contains(&name)
+        })
+    }
+
+    /// Computes the signature while serializing the parameters `in_query` accepts
+    /// into the URL and the others into a form body.
+    fn serialize<F>(
+        &self,
+        key: &SigningKey,
+        base_url: &str,
+        api_path: Option<&str>,
+        in_query: F,
+    ) -> (String, String)
+    where
+        F: Fn(&str) -> bool,
+    {
+        let capacity = self
+            .entries
+            .iter()
+            .filter(|(k, _)| in_query(k))
+            .map(|(k, v)| k.len() + v.len() + 2)
+            .sum::<usize>()
+            + base_url.len()
+            + 70;
+        let mut url = String::with_capacity(capacity);
+        url.push_str(base_url);
+        let mut body = String::new();
+
+        let mut separator = '?';
+        let digest = self.digest(key, api_path, |(name, value)| {
+            let out = if in_query(name) {
+                url.push(separator);
+                separator = '&';
+                &mut url
+            } else {
+                if !body.is_empty() {
+                    body.push('&');
+                }
+                &mut body
+            };
+            out.push_str(&encode(name));
+            out.push('=');
+            out.push_str(&encode(value));
+        });
+        url.push(separator);
+        url.push_str("sign=");
+        push_hex(&mut url, &digest);
+
+        (url, body)
+    }
+
+    /// Identifies requests that only differ by `timestamp`, for coalescing.
+    ///
+    /// The key is a SHA-256 of the parameters, so it can be logged without leaking
+    /// the `access_token`.
+    pub(crate) fn dedup_key(&self) -> String {
+        let mut hasher = Sha256::new();
+        for (name, value) in self.iter().filter(|(name, _)| *name != "timestamp") {
+            hasher.update(name.as_bytes());
+            hasher.update(b"=");
+            hasher.update(value.as_bytes());
+            hasher.update(b"&");
+        }
+
+        let mut key = String::with_capacity(64);
+        push_hex(&mut key, &hasher.finalize());
+        key
+    }
+
+    /// Feeds the signed string into the HMAC, handing each parameter to `visit`
+    /// on the way, and returns the digest.
+    fn digest<F>(&self, key: &SigningKey, api_path: Option<&str>, mut visit: F) -> [u8; 32]
+    where
+        F: FnMut((&str, &str)),
+    {
+        let mut mac = key.mac();
+        if let Some(path) = api_path {
+            mac.update(path.as_bytes());
+        }
+        for (name, value) in self.iter() {
+            mac.update(name.as_bytes());
+            mac.update(value.as_bytes());
+            visit((name, value));
+        }
+
+        mac.finalize().into_bytes().into()
+    }
+}
+
+fn push_hex(out: &mut String, bytes: &[u8]) {
+    for byte in bytes {
+        let _ = write!(out, "{:02X}", byte);
+    }
+}
+
+impl<'a, K, V> FromIterator<(K, V)> for SignedParams<'a>
+where
+    K: Into<Cow<'a, str>>,
+    V: Into<Cow<'a, str>>,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut params = SignedParams::new();
+        for (key, value) in iter {
+            params.insert(key, value);
+        }
+        params
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const APP_SECRET: &str = "0123456789abcdef0123456789abcdef";
+    const BASE_URL: &str = "https://open-api.alibaba.com/sync";
+
+    /// The signature as computed before `SignedParams`: every name and value
+    /// concatenated in name order, prefixed with the API path, under HMAC-SHA256.
+    fn reference_sign(params: &[(&str, &str)], api_path: Option<&str>) -> String {
+        let mut sorted = params.to_vec();
+        sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut concatenated = api_path.unwrap_or_default().to_string();
+        for (name, value) in sorted {
+            concatenated.push_str(name);
+            concatenated.push_str(value);
+        }
+
+        let mut mac = HmacSha256::new_from_slice(APP_SECRET.as_bytes()).unwrap();
+        mac.update(concatenated.as_bytes());
+        format!("{:X}", mac.finalize().into_bytes())
+    }
+
+    fn sample() -> Vec<(&'static str, &'static str)> {
+        vec![
+            ("timestamp", "1735689600000"),
+            ("method", "alibaba.icbu.category.attribute.get"),
+            ("app_key", "500000"),
+            ("sign_method", "sha256"),
+            ("country_request", r#"{"language":"en_US","page":1}"#),
+            ("cat_id", "100003070"),
+        ]
+    }
+
+    fn query_param<'u>(url: &'u str, name: &str) -> Option<&'u str> {
+        url.split_once('?')?
+            .1
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(name)?.strip_prefix('='))
+    }
+
+    #[test]
+    fn signature_matches_reference() {
+        let key = SigningKey::new(APP_SECRET);
+        let params: SignedParams = sample().into_iter().collect();
+
+        let expected = reference_sign(&sample(), None);
+        assert_eq!(params.sign(&key, None), expected);
+
+        let url = params.to_url(&key, BASE_URL, None);
+        assert_eq!(query_param(&url, "sign"), Some(expected.as_str()));
+    }
+
+    #[test]
+    fn api_path_is_signed_first() {
+        let key = SigningKey::new(APP_SECRET);
+        let params: SignedParams = sample().into_iter().collect();
+        let path = "/auth/token/create";
+
+        let signed = params.sign(&key, Some(path));
+        assert_eq!(signed, reference_sign(&sample(), Some(path)));
+        assert_ne!(signed, params.sign(&key, None));
+
+        let url = params.to_url(&key, BASE_URL, Some(path));
+        assert_eq!(query_param(&url, "sign"), Some(signed.as_str()));
+    }
+
+    #[test]
+    fn keys_stay_sorted() {
+        let params: SignedParams = sample().into_iter().collect();
+
+        let names: Vec<&str> = params.iter().map(|(name, _)| name).collect();
+        let mut sorted = names.clone();
+        sorted.sort_unstable();
+        assert_eq!(names, sorted);
+    }
+
+    #[test]
+    fn insert_replaces_existing_key() {
+        let mut params: SignedParams = sample().into_iter().collect();
+        params.insert("cat_id", "1");
+
+        assert_eq!(params.len(), sample().len());
+        assert_eq!(params.get("cat_id"), Some("1"));
+    }
+
+    #[test]
+    fn values_are_encoded_in_url_but_signed_raw() {
+        let key = SigningKey::new(APP_SECRET);
+        let raw = r#"{"name":"Navy Blue & Red","tags":["a/b"]}"#;
+        let mut params = SignedParams::new();
+        params.insert("app_key", "500000");
+        params.insert("param", raw);
+
+        let url = params.to_url(&key, BASE_URL, None);
+        let encoded = query_param(&url, "param").unwrap();
+        assert_eq!(encoded, encode(raw));
+        assert!(!encoded.contains(['"', ' ', '&', '/']));
+
+        let expected = reference_sign(&[("app_key", "500000"), ("param", raw)], None);
+        assert_eq!(query_param(&url, "sign"), Some(expected.as_str()));
+    }
+
+    #[test]
+    fn form_keeps_business_params_in_body() {
+        let key = SigningKey::new(APP_SECRET);
+        let params: SignedParams = sample().into_iter().collect();
+
+        let (url, body) = params.to_form(&key, BASE_URL, None);
+
+        assert_eq!(query_param(&url, "method"), sample_value("method"));
+        assert_eq!(query_param(&url, "timestamp"), sample_value("timestamp"));
+        assert_eq!(query_param(&url, "cat_id"), None);
+        assert_eq!(query_param(&url, "country_request"), None);
+        assert_eq!(
+            body,
+            format!(
+                "cat_id=100003070&country_request={}",
+                encode(r#"{"language":"en_US","page":1}"#)
+            )
+        );
+
+        let expected = reference_sign(&sample(), None);
+        assert_eq!(query_param(&url, "sign"), Some(expected.as_str()));
+    }
+
+    fn sample_value(name: &str) -> Option<&'static str> {
+        sample()
+            .into_iter()
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value)
+    }
+
+    #[test]
+    fn dedup_key_ignores_timestamp_and_hides_token() {
+        let mut params: SignedParams = sample().into_iter().collect();
+        params.insert("access_token", "secret-token");
+        let key = params.dedup_key();
+
+        params.insert("timestamp", "1735689699999");
+        assert_eq!(params.dedup_key(), key);
+        assert!(!key.contains("secret-token"));
+
+        params.insert("cat_id", "1");
+        assert_ne!(params.dedup_key(), key);
+    }
+}
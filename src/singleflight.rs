@@ -0,0 +1,209 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+
+use bytes::Bytes;
+use log::info;
+use tokio::sync::oneshot;
+
+type Shared = Result<Bytes, String>;
+
+/// Coalesces identical in-flight requests.
+///
+/// The first caller for a key becomes the leader and performs the request; every
+/// caller arriving while it is still running waits for the leader and receives a
+/// copy of its result. Once the leader finishes, the key is released, so later
+/// calls hit the gateway again.
+#[derive(Clone, Default)]
+pub struct Group {
+    calls: Arc<Mutex<HashMap<String, Vec<oneshot::Sender<Shared>>>>>,
+}
+
+enum Role {
+    Leader,
+    Waiter(oneshot::Receiver<Shared>),
+}
+
+/// Releases the key if the leader is dropped before it completes, so waiters
+/// do not hang and the next caller can take over.
+struct LeaderGuard<'a> {
+    group: &'a Group,
+    key: Option<&'a str>,
+}
+
+impl LeaderGuard<'_> {
+    fn complete(mut self) -> Vec<oneshot::Sender<Shared>> {
+        let key = self.key.take().unwrap();
+        self.group
+            .calls
+            .lock()
+            .unwrap()
+            .remove(key)
+            .unwrap_or_default()
+    }
+}
+
+impl Drop for LeaderGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.group.calls.lock().unwrap().remove(key);
+        }
+    }
+}
+
+impl Group {
+    /// Runs `f` for `key` unless an identical call is already in flight, in which
+    /// case the result of that call is awaited and shared.
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - Identifies requests that are interchangeable.
+    /// * `f` - Produces the future performing the request.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the response body if successful, or an error if the
+    /// request (either our own or the one we joined) fails.
+    pub async fn run<F, Fut>(&self, key: String, f: F) -> Result<Bytes, Box<dyn std::error::Error>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Shared>,
+    {
+        let role = {
+            let mut calls = self.calls.lock().unwrap();
+            match calls.get_mut(&key) {
+                Some(waiters) => {
+                    let (tx, rx) = oneshot::channel();
+                    waiters.push(tx);
+                    Role::Waiter(rx)
+                }
+                None => {
+                    calls.insert(key.clone(), Vec::new());
+                    Role::Leader
+                }
+            }
+        };
+
+        if let Role::Waiter(rx) = role {
+            info!(
+                "--------singleflight-------- joined in-flight request: {}",
+                key
+            );
+            return match rx.await {
+                Ok(result) => result.map_err(|err| err.into()),
+                // The leader was cancelled before finishing.
+                Err(_) => Err("In-flight request was cancelled".into()),
+            };
+        }
+
+        let guard = LeaderGuard {
+            group: self,
+            key: Some(&key),
+        };
+        let result = f().await;
+
+        for tx in guard.complete() {
+            let _ = tx.send(result.clone());
+        }
+
+        result.map_err(|err| err.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use futures::future::join_all;
+    use tokio::sync::Notify;
+
+    use super::*;
+
+    const WAITERS: usize = 8;
+
+    #[tokio::test]
+    async fn concurrent_calls_run_once() {
+        let group = Group::default();
+        let calls = AtomicUsize::new(0);
+        let release = Notify::new();
+
+        let runs = (0..WAITERS).map(|_| {
+            group.run("key".to_string(), || async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                release.notified().await;
+                Ok(Bytes::from_static(b"body"))
+            })
+        });
+        let (results, _) = futures::join!(join_all(runs), async { release.notify_one() });
+
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        for result in results {
+            assert_eq!(result.unwrap(), Bytes::from_static(b"body"));
+        }
+    }
+
+    #[tokio::test]
+    async fn errors_reach_every_waiter() {
+        let group = Group::default();
+        let release = Notify::new();
+
+        let runs = (0..WAITERS).map(|_| {
+            group.run("key".to_string(), || async {
+                release.notified().await;
+                Err("gateway down".to_string())
+            })
+        });
+        let (results, _) = futures::join!(join_all(runs), async { release.notify_one() });
+
+        assert_eq!(results.len(), WAITERS);
+        for result in results {
+            assert_eq!(result.unwrap_err().to_string(), "gateway down");
+        }
+    }
+
+    #[tokio::test]
+    async fn cancelled_leader_fails_waiters() {
+        let group = Group::default();
+
+        let mut leader = Box::pin(group.run("key".to_string(), futures::future::pending::<Shared>));
+        assert!(futures::poll!(&mut leader).is_pending());
+
+        let mut waiter = Box::pin(group.run("key".to_string(), || async {
+            unreachable!("the waiter must join the leader")
+        }));
+        assert!(futures::poll!(&mut waiter).is_pending());
+
+        drop(leader);
+        let err = waiter.await.unwrap_err();
+        assert_eq!(err.to_string(), "In-flight request was cancelled");
+
+        // The key was released, so the next call runs again.
+        let result = group
+            .run("key".to_string(), || async {
+                Ok(Bytes::from_static(b"retry"))
+            })
+            .await;
+        assert_eq!(result.unwrap(), Bytes::from_static(b"retry"));
+    }
+
+    #[tokio::test]
+    async fn key_is_released_after_completion() {
+        let group = Group::default();
+        let calls = AtomicUsize::new(0);
+
+        for _ in 0..2 {
+            group
+                .run("key".to_string(), || async {
+                    calls.fetch_add(1, Ordering::SeqCst);
+                    Ok(Bytes::new())
+                })
+                .await
+                .unwrap();
+        }
+
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert!(group.calls.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,454 @@
+use std::{collections::BTreeMap, fmt};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    attribute_validation::{check_value, AttributeViolation, ProductAttributeValue},
+    product_category::{CategoryAttribute, CategoryAttributeGroup},
+};
+
+/// A value chosen for a SKU attribute.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SkuValue {
+    pub value: ProductAttributeValue,
+
+    /// The image shown for this value, e.g. a photobank URL. Only accepted by
+    /// attributes with `customize_image`.
+    pub image: Option<String>,
+}
+
+impl SkuValue {
+    /// Creates a SKU value without image.
+    pub fn new(value: ProductAttributeValue) -> Self {
+        SkuValue { value, image: None }
+    }
+
+    /// Attaches an image to the value.
+    pub fn with_image(mut self, image: impl Into<String>) -> Self {
+        self.image = Some(image.into());
+        self
+    }
+}
+
+/// One attribute value of a generated SKU.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SkuAttributeValue {
+    pub attr_id: i32,
+    pub value: ProductAttributeValue,
+    pub image: Option<String>,
+}
+
+/// A variant of a product, i.e. one combination of SKU attribute values.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Sku {
+    /// Identifies the combination independently of the order values were added in,
+    /// e.g. `100:200;300:c:Navy Blue`. `%`, `:` and `;` in custom values are
+    /// percent-encoded.
+    pub key: String,
+    pub values: Vec<SkuAttributeValue>,
+}
+
+/// A reason the chosen SKU attributes cannot be used.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub enum SkuViolation {
+    /// The attribute does not define variants.
+    NotSkuAttribute { attr_id: i32, name: String },
+    /// A required SKU attribute has no value.
+    MissingRequired { attr_id: i32, name: String },
+    /// A predefined value cannot be used for variants.
+    NotSkuValue {
+        attr_id: i32,
+        name: String,
+        attr_value_id: i32,
+    },
+    /// An image was attached to a value of an attribute without `customize_image`.
+    CustomImageNotAllowed { attr_id: i32, name: String },
+    /// The same value was given twice, which would produce duplicate SKUs.
+    DuplicateValue { attr_id: i32, name: String },
+    /// The value breaks an attribute rule.
+    Attribute(AttributeViolation),
+}
+
+impl fmt::Display for SkuViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SkuViolation::NotSkuAttribute { attr_id, name } => {
+                write!(f, "Attribute {name} ({attr_id}) is not a SKU attribute")
+            }
+            SkuViolation::MissingRequired { attr_id, name } => {
+                write!(f, "SKU attribute {name} ({attr_id}) is required")
+            }
+            SkuViolation::NotSkuValue {
+                attr_id,
+                name,
+                attr_value_id,
+            } => write!(
+                f,
+                "Value {attr_value_id} of attribute {name} ({attr_id}) cannot define a SKU"
+            ),
+            SkuViolation::CustomImageNotAllowed { attr_id, name } => {
+                write!(f, "Attribute {name} ({attr_id}) does not accept images")
+            }
+            SkuViolation::DuplicateValue { attr_id, name } => {
+                write!(f, "Attribute {name} ({attr_id}) has a duplicate value")
+            }
+            SkuViolation::Attribute(violation) => violation.fmt(f),
+        }
+    }
+}
+
+/// Builds the SKU matrix of a product from the category's SKU attributes.
+///
+/// Values are set per attribute with `attribute`, then `build` validates them and
+/// generates the cartesian product.
+#[derive(Debug, Clone)]
+pub struct SkuBuilder<'a> {
+    schema: &'a CategoryAttributeGroup,
+    attributes: BTreeMap<i32, Vec<SkuValue>>,
+}
+
+impl<'a> SkuBuilder<'a> {
+    /// Creates a builder for a category.
+    ///
+    /// # Arguments
+    ///
+    /// * `schema` - The category attributes, as returned by `get_category_attributes`.
+    pub fn new(schema: &'a CategoryAttributeGroup) -> Self {
+        SkuBuilder {
+            schema,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the chosen values of a SKU attribute, replacing previous ones.
+    ///
+    /// # Arguments
+    ///
+    /// * `attr_id` - The `CategoryAttribute.attr_id` of a SKU attribute.
+    /// * `values` - The values to combine with the other attributes.
+    pub fn attribute(mut self, attr_id: i32, values: Vec<SkuValue>) -> Self {
+        self.attributes.insert(attr_id, values);
+        self
+    }
+
+    /// Checks the chosen values against the SKU attribute rules.
+    ///
+    /// # Returns
+    ///
+    /// Every violation found, empty if the SKU matrix can be built.
+    pub fn validate(&self) -> Vec<SkuViolation> {
+        let mut violations = Vec::new();
+
+        for attribute in &self.schema.attributes.attribute {
+            let has_value = self
+                .attributes
+                .get(&attribute.attr_id)
+                .map_or(false, |values| !values.is_empty());
+            if attribute.sku_attribute && attribute.required && !has_value {
+                violations.push(SkuViolation::MissingRequired {
+                    attr_id: attribute.attr_id,
+                    name: attribute.en_name.clone(),
+                });
+            }
+        }
+
+        for (attr_id, values) in &self.attributes {
+            let attribute = match self.schema.attribute(*attr_id) {
+                Some(attribute) => attribute,
+                None => {
+                    violations.push(SkuViolation::Attribute(
+                        AttributeViolation::UnknownAttribute { attr_id: *attr_id },
+                    ));
+                    continue;
+                }
+            };
+
+            if !attribute.sku_attribute {
+                violations.push(SkuViolation::NotSkuAttribute {
+                    attr_id: *attr_id,
+                    name: attribute.en_name.clone(),
+                });
+                continue;
+            }
+
+            for (index, value) in values.iter().enumerate() {
+                if values[..index]
+                    .iter()
+                    .any(|other| other.value == value.value)
+                {
+                    violations.push(SkuViolation::DuplicateValue {
+                        attr_id: *attr_id,
+                        name: attribute.en_name.clone(),
+                    });
+                }
+                if let Some(violation) = check_sku_value(attribute, value) {
+                    violations.push(violation);
+                }
+            }
+        }
+
+        violations
+    }
+
+    /// Generates every combination of the chosen values.
+    ///
+    /// Attributes are combined in `attr_id` order and values in the order given,
+    /// so the output is stable for the same input.
+    ///
+    /// # Returns
+    ///
+    /// A `Result` containing the SKUs if the values are valid, or every violation
+    /// found otherwise.
+    pub fn build(&self) -> Result<Vec<Sku>, Vec<SkuViolation>> {
+        let violations = self.validate();
+        if !violations.is_empty() {
+            return Err(violations);
+        }
+
+        let mut skus = vec![Sku {
+            key: String::new(),
+            values: Vec::new(),
+        }];
+        for (attr_id, values) in &self.attributes {
+            if values.is_empty() {
+                continue;
+            }
+
+            let mut next = Vec::with_capacity(skus.len() * values.len());
+            for sku in &skus {
+                for value in values {
+                    let mut sku = sku.clone();
+                    if !sku.key.is_empty() {
+                        sku.key.push(';');
+                    }
+                    sku.key.push_str(&key_part(*attr_id, &value.value));
+                    sku.values.push(SkuAttributeValue {
+                        attr_id: *attr_id,
+                        value: value.value.clone(),
+                        image: value.image.clone(),
+                    });
+                    next.push(sku);
+                }
+            }
+            skus = next;
+        }
+
+        skus.retain(|sku| !sku.values.is_empty());
+        Ok(skus)
+    }
+}
+
+fn check_sku_value(attribute: &CategoryAttribute, value: &SkuValue) -> Option<SkuViolation> {
+    if let Some(violation) = check_value(attribute, &value.value) {
+        return Some(SkuViolation::Attribute(violation));
+    }
+
+    if let ProductAttributeValue::Id(attr_value_id) = value.value {
+        let sku_value = attribute
+            .attribute_values
+            .as_ref()
+            .and_then(|values| {
+                values
+                    .attribute_value
+                    .iter()
+                    .find(|value| value.attr_value_id == attr_value_id)
+            })
+            .map_or(false, |value| value.sku_value);
+        if !sku_value {
+            return Some(SkuViolation::NotSkuValue {
+                attr_id: attribute.attr_id,
+                name: attribute.en_name.clone(),
+                attr_value_id,
+            });
+        }
+    }
+
+    if value.image.is_some() && !attribute.customize_image {
+        return Some(SkuViolation::CustomImageNotAllowed {
+            attr_id: attribute.attr_id,
+            name: attribute.en_name.clone(),
+        });
+    }
+
+    None
+}
+
+fn key_part(attr_id: i32, value: &ProductAttributeValue) -> String {
+    match value {
+        ProductAttributeValue::Id(attr_value_id) => format!("{}:{}", attr_id, attr_value_id),
+        ProductAttributeValue::Custom(value) => format!("{}:c:{}", attr_id, escape_key(value)),
+    }
+}
+
+/// Percent-encodes the characters that structure a SKU key, so a custom value
+/// cannot be mistaken for a separator.
+fn escape_key(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '%' => escaped.push_str("%25"),
+            ':' => escaped.push_str("%3A"),
+            ';' => escaped.push_str("%3B"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLOR: i32 = 100;
+    const SIZE: i32 = 300;
+
+    fn schema() -> CategoryAttributeGroup {
+        serde_json::from_value(serde_json::json!({
+            "attributes": {
+                "attribute": [
+                    {
+                        "attr_id": COLOR,
+                        "en_name": "Color",
+                        "sku_attribute": true,
+                        "required": true,
+                        "show_type": "check_box",
+                        "input_type": "multi_select",
+                        "value_type": "string",
+                        "customize_image": true,
+                        "customize_value": true,
+                        "car_model": false,
+                        "attribute_values": {
+                            "attribute_value": [
+                                { "attr_value_id": 1, "en_name": "Red", "sku_value": true },
+                                { "attr_value_id": 2, "en_name": "Blue", "sku_value": true },
+                            ],
+                        },
+                    },
+                    {
+                        "attr_id": SIZE,
+                        "en_name": "Size",
+                        "sku_attribute": true,
+                        "required": false,
+                        "show_type": "input",
+                        "input_type": "input",
+                        "value_type": "string",
+                        "customize_image": false,
+                        "customize_value": true,
+                        "car_model": false,
+                        "attribute_values": {},
+                    },
+                ],
+            },
+        }))
+        .unwrap()
+    }
+
+    fn id(attr_value_id: i32) -> SkuValue {
+        SkuValue::new(ProductAttributeValue::Id(attr_value_id))
+    }
+
+    fn custom(value: &str) -> SkuValue {
+        SkuValue::new(ProductAttributeValue::Custom(value.to_string()))
+    }
+
+    fn keys(skus: &[Sku]) -> Vec<&str> {
+        skus.iter().map(|sku| sku.key.as_str()).collect()
+    }
+
+    #[test]
+    fn builds_cartesian_product_in_attribute_order() {
+        let schema = schema();
+        let skus = SkuBuilder::new(&schema)
+            .attribute(SIZE, vec![custom("S"), custom("M"), custom("L")])
+            .attribute(COLOR, vec![id(1), id(2)])
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            keys(&skus),
+            [
+                "100:1;300:c:S",
+                "100:1;300:c:M",
+                "100:1;300:c:L",
+                "100:2;300:c:S",
+                "100:2;300:c:M",
+                "100:2;300:c:L",
+            ]
+        );
+        assert_eq!(
+            skus[4].values,
+            [
+                SkuAttributeValue {
+                    attr_id: COLOR,
+                    value: ProductAttributeValue::Id(2),
+                    image: None,
+                },
+                SkuAttributeValue {
+                    attr_id: SIZE,
+                    value: ProductAttributeValue::Custom("M".to_string()),
+                    image: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn key_keeps_plain_custom_values_readable() {
+        let schema = schema();
+        let skus = SkuBuilder::new(&schema)
+            .attribute(COLOR, vec![custom("Navy Blue")])
+            .build()
+            .unwrap();
+
+        assert_eq!(keys(&skus), ["100:c:Navy Blue"]);
+    }
+
+    #[test]
+    fn key_escapes_separators_in_custom_values() {
+        let schema = schema();
+        let skus = SkuBuilder::new(&schema)
+            .attribute(COLOR, vec![custom("Navy;300:c:Blue"), custom("100%")])
+            .build()
+            .unwrap();
+
+        assert_eq!(keys(&skus), ["100:c:Navy%3B300%3Ac%3ABlue", "100:c:100%25"]);
+    }
+
+    #[test]
+    fn custom_values_cannot_collide_with_other_combinations() {
+        let schema = schema();
+        let single = SkuBuilder::new(&schema)
+            .attribute(COLOR, vec![custom("Navy;300:c:Blue")])
+            .build()
+            .unwrap();
+        let combined = SkuBuilder::new(&schema)
+            .attribute(COLOR, vec![custom("Navy")])
+            .attribute(SIZE, vec![custom("Blue")])
+            .build()
+            .unwrap();
+        let escaped = SkuBuilder::new(&schema)
+            .attribute(COLOR, vec![custom("Navy%3B300%3Ac%3ABlue")])
+            .build()
+            .unwrap();
+
+        assert_ne!(single[0].key, combined[0].key);
+        assert_ne!(single[0].key, escaped[0].key);
+    }
+
+    #[test]
+    fn missing_required_attribute_is_reported() {
+        let schema = schema();
+        let violations = SkuBuilder::new(&schema)
+            .attribute(SIZE, vec![custom("S")])
+            .build()
+            .unwrap_err();
+
+        assert_eq!(
+            violations,
+            [SkuViolation::MissingRequired {
+                attr_id: COLOR,
+                name: "Color".to_string(),
+            }]
+        );
+    }
+}
@@ -1,14 +1,22 @@
 use crate::{
-    constants::{keys, methods, urls},
+    constants::{caches, keys, methods, urls},
     IopClient,
 };
-use deadpool_redis::redis::cmd;
-use log::{error, info};
+use deadpool_redis::redis::{self, cmd};
+use futures::{
+    future::{self, Either},
+    StreamExt,
+};
+use log::{error, info, warn};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::{
+    sync::{Arc, RwLock},
+    time::{Duration, Instant},
+};
+use tokio::{sync::oneshot, task::JoinHandle};
 use urlencoding::encode;
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CountryUserInfo {
     #[serde(rename = "aliId")]
     pub union_id: String,
@@ -21,7 +29,7 @@ pub struct CountryUserInfo {
     pub seller_id: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AccessToken {
     pub access_token: String,
     pub refresh_token: String,
@@ -40,7 +48,179 @@ pub struct AccessToken {
     pub email: String,
 }
 
+/// In-process copy of the access token stored in Redis.
+///
+/// Entries expire after a minute, and are dropped early whenever any instance
+/// publishes on the invalidation channel after refreshing or replacing the token.
+/// Dropping the last clone stops the invalidation listener.
+#[derive(Clone)]
+pub(crate) struct TokenCache {
+    state: Arc<RwLock<CacheState>>,
+    _shutdown: Arc<oneshot::Sender<()>>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    entry: Option<(AccessToken, Instant)>,
+    /// Bumped by every invalidation, so a token read before one is not cached.
+    generation: u64,
+}
+
+impl CacheState {
+    fn invalidate(&mut self) {
+        self.entry = None;
+        self.generation += 1;
+    }
+}
+
+impl TokenCache {
+    /// Creates an empty cache, with the receiver that resolves once every clone
+    /// of it has been dropped.
+    pub(crate) fn new() -> (Self, oneshot::Receiver<()>) {
+        let (tx, rx) = oneshot::channel();
+        let cache = TokenCache {
+            state: Arc::default(),
+            _shutdown: Arc::new(tx),
+        };
+        (cache, rx)
+    }
+
+    fn get(&self) -> Option<AccessToken> {
+        let ttl = Duration::from_secs(caches::ONE_MINUTE_IN_SECONDS);
+        match &self.state.read().unwrap().entry {
+            Some((at, cached_at)) if cached_at.elapsed() < ttl => Some(at.clone()),
+            _ => None,
+        }
+    }
+
+    /// Returns the current generation, to pass to `set` once the token is read.
+    fn generation(&self) -> u64 {
+        self.state.read().unwrap().generation
+    }
+
+    /// Caches a token read from Redis, unless an invalidation arrived since
+    /// `generation` was taken, in which case the token may already be stale.
+    fn set(&self, at: AccessToken, generation: u64) {
+        let mut state = self.state.write().unwrap();
+        if state.generation == generation {
+            state.entry = Some((at, Instant::now()));
+        }
+    }
+
+    /// Caches a token this instance just stored, discarding reads in flight.
+    fn replace(&self, at: AccessToken) {
+        let mut state = self.state.write().unwrap();
+        state.invalidate();
+        state.entry = Some((at, Instant::now()));
+    }
+}
+
+/// Clears the cache on every message published to `channel`.
+///
+/// Returns once the connection is lost.
+async fn watch_invalidations(
+    redis_addr: &str,
+    channel: &str,
+    state: &RwLock<CacheState>,
+) -> redis::RedisResult<()> {
+    let client = redis::Client::open(redis_addr)?;
+    let mut pubsub = client.get_async_pubsub().await?;
+    pubsub.subscribe(channel).await?;
+
+    let mut messages = pubsub.on_message();
+    while messages.next().await.is_some() {
+        state.write().unwrap().invalidate();
+    }
+
+    Ok(())
+}
+
+/// Keeps `watch_invalidations` running, reconnecting after a second when the
+/// subscription is lost, until `shutdown` resolves.
+fn spawn_invalidation_listener(
+    redis_addr: String,
+    channel: String,
+    state: Arc<RwLock<CacheState>>,
+    mut shutdown: oneshot::Receiver<()>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            let watch = Box::pin(watch_invalidations(&redis_addr, &channel, &state));
+            match future::select(&mut shutdown, watch).await {
+                Either::Left(_) => return,
+                Either::Right((Err(err), _)) => {
+                    warn!("Access token invalidation subscription lost, {err}");
+                }
+                Either::Right((Ok(()), _)) => {}
+            }
+
+            state.write().unwrap().invalidate();
+
+            let retry = Box::pin(tokio::time::sleep(Duration::from_secs(1)));
+            if let Either::Left(_) = future::select(&mut shutdown, retry).await {
+                return;
+            }
+        }
+    })
+}
+
 impl IopClient {
+    /// Subscribes to the access token invalidation channel in the background.
+    ///
+    /// Whenever an instance sharing this Redis stores a new access token, the
+    /// in-process copy is dropped so the next call reads the new one. While the
+    /// subscription is down, invalidations may be missed, so the cache is cleared
+    /// before every reconnect attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `redis_addr` - The address of the Redis server to subscribe on.
+    /// * `shutdown` - The receiver returned by `TokenCache::new`; the subscription
+    ///   ends when it resolves.
+    pub(crate) fn listen_token_invalidations(
+        &self,
+        redis_addr: String,
+        shutdown: oneshot::Receiver<()>,
+    ) {
+        let channel = format!("{}:{}", keys::ACCESS_TOKEN_INVALIDATE, self.appid);
+        spawn_invalidation_listener(
+            redis_addr,
+            channel,
+            self.token_cache.state.clone(),
+            shutdown,
+        );
+    }
+
+    /// Stores the access token in Redis and in the in-process cache, then notifies
+    /// the other instances so they drop their stale copies.
+    ///
+    /// # Arguments
+    ///
+    /// * `at` - The access token to store.
+    async fn store_access_token(&self, at: &AccessToken) {
+        let key = format!("{}:{}", keys::ACCESS_TOKEN, self.appid);
+        let channel = format!("{}:{}", keys::ACCESS_TOKEN_INVALIDATE, self.appid);
+        if let Ok(mut conn) = self.pool.get().await {
+            let _: () = cmd("SET")
+                .arg(&key)
+                .arg(serde_json::to_string(at).unwrap())
+                .query_async::<()>(&mut conn)
+                .await
+                .unwrap();
+
+            if let Err(err) = cmd("PUBLISH")
+                .arg(&channel)
+                .arg(&self.appid)
+                .query_async::<()>(&mut conn)
+                .await
+            {
+                warn!("Failed to publish access token invalidation, {err}");
+            }
+        }
+
+        self.token_cache.replace(at.clone());
+    }
+
     /// Constructs and returns a redirect URL for the OAuth authorization process.
     ///
     /// # Arguments
@@ -81,16 +261,13 @@ impl IopClient {
         &self,
         code: String,
     ) -> Result<AccessToken, Box<dyn std::error::Error>> {
-        let mut map = HashMap::new();
-        map.insert("code".to_string(), code);
-
-        let params = self.build_request_params(map).await;
+        let mut params = self.build_signed_params().await;
+        params.insert("code", code);
 
-        let hash = self.generate_sign(Some(methods::AUTH_TOKEN_CREATE.to_string()), params.clone());
-        let url = self.generate_url(
-            urls::AUTH_TOKEN_CREATE_URL.to_string(),
-            params.clone(),
-            hash,
+        let url = params.to_url(
+            &self.signing_key,
+            urls::AUTH_TOKEN_CREATE_URL,
+            Some(methods::AUTH_TOKEN_CREATE),
         );
         info!("--------generate_access_token-------- url: {:#?}", url);
 
@@ -110,31 +287,34 @@ impl IopClient {
             }
         };
 
-        let key = format!("{}:{}", keys::ACCESS_TOKEN, self.appid);
-        if let Ok(mut conn) = self.pool.get().await {
-            let _: () = cmd("SET")
-                .arg(&key)
-                .arg(serde_json::to_string(&at).unwrap())
-                .query_async::<()>(&mut conn)
-                .await
-                .unwrap();
-        }
+        self.store_access_token(&at).await;
 
         Ok(at)
     }
 
-    /// Retrieves the access token associated with the client from Redis.
+    /// Retrieves the access token associated with the client.
+    ///
+    /// The in-process copy is used while it is fresh; otherwise the token is read
+    /// from Redis and cached in process, unless an invalidation arrived during the
+    /// read.
     ///
     /// # Returns
     ///
     /// A `Result` containing the `AccessToken` model if the token exists, or an error if the token
     /// is not found in Redis.
     pub async fn get_access_token(&self) -> Result<AccessToken, Box<dyn std::error::Error>> {
+        if let Some(at) = self.token_cache.get() {
+            return Ok(at);
+        }
+
         let key = format!("{}:{}", keys::ACCESS_TOKEN, self.appid);
+        let generation = self.token_cache.generation();
         if let Ok(mut conn) = self.pool.get().await {
             let at: Option<String> = cmd("GET").arg(&key).query_async(&mut conn).await.unwrap();
             if let Some(at) = at {
-                return Ok(serde_json::from_str(&at).unwrap());
+                let at: AccessToken = serde_json::from_str(&at).unwrap();
+                self.token_cache.set(at.clone(), generation);
+                return Ok(at);
             }
         }
 
@@ -160,20 +340,15 @@ impl IopClient {
             Ok(token) => token,
             Err(err) => {
                 error!("Failed to get access token, {err}");
-                return Err(err.into());
+                return Err(err);
             }
         };
 
-        let mut map = HashMap::new();
-        map.insert("refresh_token".to_string(), token.refresh_token.clone());
-        map.insert(
-            "method".to_string(),
-            methods::AUTH_TOKEN_REFRESH.to_string(),
-        );
-        let params = self.build_request_params(map).await;
+        let mut params = self.build_signed_params().await;
+        params.insert("refresh_token", token.refresh_token);
+        params.insert("method", methods::AUTH_TOKEN_REFRESH);
 
-        let hash = self.generate_sign(None, params.clone());
-        let url = self.generate_url(urls::BASE_SYNC_URL.to_string(), params.clone(), hash);
+        let url = params.to_url(&self.signing_key, urls::BASE_SYNC_URL, None);
         info!("--------refresh_access_token-------- url: {:#?}", url);
 
         let response = match self.client.get(&url).send().await {
@@ -191,17 +366,96 @@ impl IopClient {
             }
         };
 
-        let key = format!("{}:{}", keys::ACCESS_TOKEN, self.appid);
-        if let Ok(mut conn) = self.pool.get().await {
-            let _: () = cmd("SET")
-                .arg(&key)
-                .arg(serde_json::to_string(&at).unwrap())
-                .query_async::<()>(&mut conn)
-                .await
-                .unwrap();
-        }
+        self.store_access_token(&at).await;
 
         // TODO: return at
         Err("Failed to refresh access token".into())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(access_token: &str) -> AccessToken {
+        serde_json::from_value(serde_json::json!({
+            "access_token": access_token,
+            "refresh_token": "refresh",
+            "refresh_expires_in": 0,
+            "expires_in": 0,
+            "code": "0",
+            "account_platform": "seller_center",
+            "country": "cn",
+            "country_user_info": {
+                "aliId": "1",
+                "loginId": "seller",
+                "user_id": "2",
+                "seller_id": "3",
+            },
+            "account": "seller@example.com",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn read_racing_an_invalidation_is_not_cached() {
+        let (cache, _shutdown) = TokenCache::new();
+
+        let generation = cache.generation();
+        // Another instance refreshes the token while the old one is being read.
+        cache.state.write().unwrap().invalidate();
+        cache.set(token("old"), generation);
+
+        assert!(cache.get().is_none());
+    }
+
+    #[test]
+    fn read_without_invalidation_is_cached() {
+        let (cache, _shutdown) = TokenCache::new();
+
+        let generation = cache.generation();
+        cache.set(token("current"), generation);
+
+        assert_eq!(cache.get().unwrap().access_token, "current");
+    }
+
+    #[test]
+    fn stored_token_wins_over_read_in_flight() {
+        let (cache, _shutdown) = TokenCache::new();
+
+        let generation = cache.generation();
+        cache.replace(token("new"));
+        cache.set(token("old"), generation);
+
+        assert_eq!(cache.get().unwrap().access_token, "new");
+    }
+
+    #[tokio::test]
+    async fn listener_stops_when_cache_is_dropped() {
+        // A server that accepts connections but never answers, like a subscription
+        // nobody publishes on.
+        let server = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let _connections: Vec<_> = server.incoming().collect();
+        });
+
+        let (cache, shutdown) = TokenCache::new();
+        let listener = spawn_invalidation_listener(
+            format!("redis://{addr}"),
+            "channel".to_string(),
+            cache.state.clone(),
+            shutdown,
+        );
+
+        // Let the listener connect and wait for messages.
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!listener.is_finished());
+
+        drop(cache);
+        tokio::time::timeout(Duration::from_secs(5), listener)
+            .await
+            .expect("listener still running after the cache was dropped")
+            .unwrap();
+    }
+}